@@ -33,6 +33,39 @@ impl FrameSetEntry {
     }
 }
 
+/// A block of transition tiles in a tileset
+#[derive(Serialize, Deserialize, Debug)]
+struct AutotileBlockDef {
+    /// The tile that will be replaced by transition tiles
+    tile: [u32; 2],
+    /// The top-left tile of the block of transition tiles
+    block: [u32; 2],
+}
+
+/// Autotiling info for a tileset. Every block in a tileset shares the same
+/// layout.
+#[derive(Serialize, Deserialize, Debug)]
+struct AutotileDef {
+    /// Offsets (in tiles, from the top-left of a block) of the tile to use for
+    /// each combination of matching neighbours, indexed by a mask where N = 1,
+    /// E = 2, S = 4 and W = 8.
+    transitions: [[u32; 2]; 16],
+    blocks: Vec<AutotileBlockDef>,
+}
+
+impl AutotileDef {
+    /// Convert this to a map of tiles to their transition tables
+    fn to_autotiles(&self) -> BTreeMap<[u32; 2], AutotileTable> {
+        self.blocks.iter().map(|b| {
+            let mut table = [[0; 2]; 16];
+            for (t, off) in table.iter_mut().zip(self.transitions.iter()) {
+                *t = [b.block[0] + off[0], b.block[1] + off[1]];
+            }
+            (b.tile, table)
+        }).collect()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 enum AssetDefinition {
     Tex {
@@ -59,6 +92,7 @@ enum AssetDefinition {
         filename: String,
         tiles_x: u32,
         tiles_y: u32,
+        autotile: Option<AutotileDef>,
     },
     BitmapFont {
         name: String,
@@ -101,6 +135,11 @@ impl AssetDefinition {
 /// Load all the game assets from the asset descriptions. Just panics if shit is fucked.
 pub fn load_assets(factory: &mut Factory) -> (TextureAtlas<TextureKey>,
                                               ShaderResourceView<Resources, [f32; 4]>) {
+    pack_assets().build(factory)
+}
+
+/// Pack all the assets into an atlas builder
+fn pack_assets() -> AtlasBuilder<TextureKey> {
     let mut builder = AtlasBuilder::<TextureKey>::new(512, 512);
     // Load all filenames & add them to one big asset list.
     let asset_list : Vec<(usize, AssetDefinition)> =
@@ -160,8 +199,10 @@ pub fn load_assets(factory: &mut Factory) -> (TextureAtlas<TextureKey>,
                     *frame_w, *frame_h).unwrap();
                 anims.insert(name, *ix);
             }
-            AssetDefinition::Tileset {filename, tiles_x, tiles_y, ..} => {
-                builder.add_tileset(*ix, filename, *tiles_x, *tiles_y).unwrap();
+            AssetDefinition::Tileset {filename, tiles_x, tiles_y, autotile, ..} => {
+                let autotiles = autotile.as_ref().map(AutotileDef::to_autotiles)
+                    .unwrap_or(BTreeMap::new());
+                builder.add_tileset(*ix, filename, *tiles_x, *tiles_y, autotiles).unwrap();
             }
             AssetDefinition::BitmapFont {filename, glyph_w, glyph_h, char_map, ..} => {
                 let char_map : Vec<(char, (u16, u16))> = char_map.iter().map(|(k, [x, y])| (*k, (*x, *y))).collect();
@@ -182,5 +223,15 @@ pub fn load_assets(factory: &mut Factory) -> (TextureAtlas<TextureKey>,
         }
     });
 
-    builder.build(factory)
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assets_fit_in_atlas() {
+        pack_assets();
+    }
 }
@@ -0,0 +1,122 @@
+use renderer::*;
+use enemies::EnemyType;
+use super::*;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ClimateDef {
+    temperature: f32,
+    moisture: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PaletteTileDef {
+    name: String,
+    x: u32,
+    y: u32,
+    #[serde(default)]
+    solid: bool,
+}
+
+impl PaletteTileDef {
+    fn link_assets(&self) -> PaletteTile {
+        PaletteTile {
+            name: self.name.clone(),
+            tile: [self.x, self.y],
+            solid: self.solid,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PropDef {
+    sprite: String,
+    width: f32,
+    height: f32,
+    collision_radius: Option<f32>,
+    collision_height: Option<f32>,
+    density: f32,
+    tiles: Vec<String>,
+}
+
+impl PropDef {
+    /// Link the sprite, and look up the tile names in the given palette
+    fn link_assets(&self, biome: &str, palette: &[PaletteTileDef]) -> PropDetails {
+        PropDetails {
+            sprite: get_asset_by_name(&self.sprite),
+            width: self.width,
+            height: self.height,
+            collision_radius: self.collision_radius,
+            collision_height: self.collision_height,
+            density: self.density,
+            tiles: self.tiles.iter().map(|t| {
+                palette.iter().position(|p| &p.name == t)
+                    .expect(&format!("Tile {} not in the palette of biome {}", t, biome))
+                    as u8
+            }).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct EnemySpawnDef {
+    enemy: EnemyType,
+    chance: f32,
+    min: u8,
+    max: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BiomeDetailsDef {
+    tileset: String,
+    climate: Option<ClimateDef>,
+    #[serde(default)]
+    water_level: f32,
+    #[serde(default)]
+    ground_moisture: f32,
+    palette: Vec<PaletteTileDef>,
+    #[serde(default)]
+    props: Vec<PropDef>,
+    #[serde(default)]
+    enemies: Vec<EnemySpawnDef>,
+}
+
+impl BiomeDetailsDef {
+    /// Create the biome details from this definition, by linking in the
+    /// texture assets and resolving tile names
+    pub fn link_assets(&self, name: String) -> BiomeDetails {
+        BiomeDetails {
+            tileset: get_asset_by_name(&self.tileset),
+            climate: self.climate.map(|c| [c.temperature, c.moisture]),
+            water_level: self.water_level,
+            ground_moisture: self.ground_moisture,
+            palette: self.palette.iter().map(|p| p.link_assets()).collect(),
+            props: self.props.iter().map(|p| p.link_assets(&name, &self.palette)).collect(),
+            enemies: self.enemies.iter().map(|e| EnemySpawn {
+                enemy: e.enemy, chance: e.chance, min: e.min, max: e.max,
+            }).collect(),
+            name: name,
+        }
+    }
+}
+
+/// Load biome defs from files. Returns a map of biome names to details.
+pub fn load_defs() -> BTreeMap<String, BiomeDetailsDef> {
+    let mut biomes : BTreeMap<String, BiomeDetailsDef> = BTreeMap::new();
+    fs::read_dir("res/biomes").unwrap().map(|entry| {
+        let entry = entry.unwrap();
+        let path = entry.path();
+        if path.is_dir() {
+            panic!("Directories not allowed in biomes directory. A \
+                    flat-file structure must be used.");
+        } else {
+            path
+        }
+    }).for_each(|path| {
+        let mut f = fs::File::open(path).unwrap();
+        let mut definitions : BTreeMap<String, BiomeDetailsDef> =
+            serde_yaml::from_reader(&mut f).unwrap();
+        biomes.append(&mut definitions);
+    });
+
+    biomes
+}
@@ -0,0 +1,183 @@
+//! A list of biomes, loaded from the res/biomes directory. A biome decides the
+//! tileset a chunk is drawn with, what the tile ids in that chunk mean, and
+//! what props and enemies are placed in it.
+#![allow(dead_code)]
+
+mod deser_structs;
+
+use serde_yaml;
+use renderer::*;
+use enemies::EnemyType;
+use std::sync::RwLock;
+use std::fs;
+use std::collections::BTreeMap;
+
+#[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq)]
+pub struct BiomeType(pub usize);
+
+/// A tile that can be placed in a tilemap of this biome. The tile id used in
+/// the Tilemap data is the index of this tile in the biome's palette.
+#[derive(Clone, Debug)]
+pub struct PaletteTile {
+    pub name: String,
+    /// X / Y position of this tile in the tileset (in tiles)
+    pub tile: [u32; 2],
+    /// Does this tile block the movement of solid colliders?
+    pub solid: bool,
+}
+
+/// A prop (tree, rock etc) that's scattered over this biome
+#[derive(Clone, Debug)]
+pub struct PropDetails {
+    pub sprite: TextureKey,
+    pub width: f32,
+    pub height: f32,
+    /// If Some, this prop is solid and static with the given collision radius
+    pub collision_radius: Option<f32>,
+    /// If Some, the collider is this tall, so airborne entities can pass over
+    /// it. Otherwise it spans every height.
+    pub collision_height: Option<f32>,
+    /// Roughly how many of this prop there are per tile
+    pub density: f32,
+    /// The tile ids this prop can be placed on
+    pub tiles: Vec<u8>,
+}
+
+/// A roll for spawning enemies when a chunk of this biome is loaded
+#[derive(Clone, Copy, Debug)]
+pub struct EnemySpawn {
+    pub enemy: EnemyType,
+    /// Chance (0 to 1) that this spawns anything in a chunk
+    pub chance: f32,
+    /// Minimum number of enemies to spawn (Inclusive)
+    pub min: u8,
+    /// Maximum number of enemies to spawn (Exclusive)
+    pub max: u8,
+}
+
+#[derive(Debug)]
+pub struct BiomeDetails {
+    name: String,
+    tileset: TextureKey,
+    /// Where this biome sits on the climate map (temperature, moisture). If
+    /// None, this biome will never be picked by the overworld generator.
+    climate: Option<[f32; 2]>,
+    /// Anything with an elevation below this is water
+    water_level: f32,
+    /// Anything (above water) with a moisture below this is bare ground
+    ground_moisture: f32,
+    palette: Vec<PaletteTile>,
+    props: Vec<PropDetails>,
+    enemies: Vec<EnemySpawn>,
+}
+
+/// The parts of a biome the overworld generator needs to pick biomes and
+/// generate tiles. This is copied out of the register so world generation
+/// doesn't need to lock it (and can be tested without loading any assets).
+#[derive(Clone, Copy, Debug)]
+pub struct BiomeClimate {
+    pub biome: BiomeType,
+    pub temperature: f32,
+    pub moisture: f32,
+    pub water_level: f32,
+    pub ground_moisture: f32,
+}
+
+struct BiomeRegister {
+    biome_list: Vec<BiomeDetails>,
+}
+
+impl BiomeRegister {
+    pub fn new() -> BiomeRegister {
+        BiomeRegister {
+            biome_list: Vec::new(),
+        }
+    }
+
+    fn get_details(&self, biome: BiomeType) -> &BiomeDetails {
+        debug_assert!(biome.0 < self.biome_list.len(), "Biome ID out of range");
+        &self.biome_list[biome.0]
+    }
+}
+
+lazy_static! {
+    /// The biome register is a list of all the biomes, loaded from the
+    /// res/biomes directory at game startup, and accessed read-only from then
+    /// on.
+    static ref BIOME_REGISTER : RwLock<BiomeRegister> =
+        RwLock::new(BiomeRegister::new());
+}
+
+impl BiomeType {
+    pub fn name(self) -> String {
+        BIOME_REGISTER.read().unwrap().get_details(self).name.clone()
+    }
+
+    /// Get the texture key of this biome's tileset
+    pub fn tileset(self) -> TextureKey {
+        BIOME_REGISTER.read().unwrap().get_details(self).tileset
+    }
+
+    /// Get the tileset position of every tile in the palette, indexed by tile
+    /// id.
+    pub fn palette(self) -> Vec<[u32; 2]> {
+        BIOME_REGISTER.read().unwrap().get_details(self)
+            .palette.iter().map(|p| p.tile).collect()
+    }
+
+    /// Get whether each tile in the palette is solid, indexed by tile id.
+    pub fn solid_tiles(self) -> Vec<bool> {
+        BIOME_REGISTER.read().unwrap().get_details(self)
+            .palette.iter().map(|p| p.solid).collect()
+    }
+
+    pub fn props(self) -> Vec<PropDetails> {
+        BIOME_REGISTER.read().unwrap().get_details(self).props.clone()
+    }
+
+    pub fn enemies(self) -> Vec<EnemySpawn> {
+        BIOME_REGISTER.read().unwrap().get_details(self).enemies.clone()
+    }
+}
+
+/// Given a string, get the biome with that name. This is a linear search, so
+/// should only be called at setup.
+pub fn get_biome_with_name(name: &str) -> Option<BiomeType> {
+    BIOME_REGISTER.read().unwrap().biome_list.iter().enumerate()
+        .find(|(_, b)| &b.name == name)
+        .map(|(ix, _)| BiomeType(ix))
+}
+
+/// Get the climate of every biome that can appear in the overworld.
+pub fn overworld_climates() -> Vec<BiomeClimate> {
+    BIOME_REGISTER.read().unwrap().biome_list.iter().enumerate()
+        .filter_map(|(ix, b)| b.climate.map(|c| BiomeClimate {
+            biome: BiomeType(ix),
+            temperature: c[0],
+            moisture: c[1],
+            water_level: b.water_level,
+            ground_moisture: b.ground_moisture,
+        })).collect()
+}
+
+/// Load the biome definitions from the res files. Assets must be loaded first.
+pub fn load_biome_definitions() {
+    let definitions = deser_structs::load_defs();
+    let definitions : Vec<BiomeDetails>
+        = definitions.iter().map(|(k, v)| v.link_assets(k.clone())).collect();
+
+    let mut br = BIOME_REGISTER.write().unwrap();
+    definitions.into_iter().for_each(|d| br.biome_list.push(d));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn biome_files_parse() {
+        let defs = deser_structs::load_defs();
+        assert!(defs.len() > 0);
+        assert!(defs.contains_key("Grassland"));
+    }
+}
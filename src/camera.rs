@@ -3,6 +3,7 @@ use input::*;
 use specs::*;
 use vec::*;
 use cgmath;
+use Interpolation;
 use std::f32::consts::PI;
 
 pub struct Camera {
@@ -64,21 +65,26 @@ pub struct FollowCameraSys;
 impl<'a> System<'a> for FollowCameraSys {
     type SystemData = (WriteExpect<'a, Camera>,
                        WriteExpect<'a, InputState>,
+                       ReadExpect<'a, Interpolation>,
+                       Entities<'a>,
                        ReadStorage<'a, Pos>,
+                       ReadStorage<'a, PrevPos>,
                        ReadStorage<'a, FollowCamera>);
 
-    fn run(&mut self, (mut camera, mut input_state, pos_s, follow_camera_s): Self::SystemData) {
-        if let Some((pos, _)) = (&pos_s, &follow_camera_s).join().next() {
-            // Update the camera size depending on view size
-            camera.w = input_state.window_size.0 as f32;
-            camera.h = input_state.window_size.1 as f32;
+    fn run(&mut self, (mut camera, mut input_state, interpolation, entities_s,
+                       pos_s, prev_pos_s, follow_camera_s): Self::SystemData) {
+        // Update the camera size depending on view size
+        camera.w = input_state.window_size.0 as f32;
+        camera.h = input_state.window_size.1 as f32;
 
-            // Update camera pos
+        // Update camera pos
+        if let Some((e, pos, _)) = (&*entities_s, &pos_s, &follow_camera_s).join().next() {
+            let pos = pos.interpolate(prev_pos_s.get(e), interpolation.0);
             camera.pos = pos.pos;
-
-            // Set world mouse position
-            input_state.world_mouse = input_state.screen_mouse + camera.pos;
         }
+
+        // Set world mouse position
+        input_state.world_mouse = input_state.screen_mouse + camera.pos;
     }
 }
 
@@ -0,0 +1,99 @@
+//! Collision layers, loaded from res/collision-layers.yaml. Layers are looked
+//! up by name to get a CollFilter for a collider.
+
+use serde_yaml;
+use comp::CollFilter;
+use std::sync::RwLock;
+use std::fs;
+use std::collections::BTreeMap;
+
+/// The most layers there can be (one for each bit of a CollFilter)
+pub const MAX_COLL_LAYERS : usize = 32;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CollLayerDef {
+    #[serde(default)]
+    collides_with: Vec<String>,
+}
+
+struct CollLayerRegister {
+    /// Layer names, mapped to the filter for colliders on that layer
+    layers: Vec<(String, CollFilter)>,
+}
+
+lazy_static! {
+    /// The collision layer register is loaded from the res files at game
+    /// startup, and accessed read-only from then on.
+    static ref COLL_LAYER_REGISTER : RwLock<CollLayerRegister> =
+        RwLock::new(CollLayerRegister { layers: Vec::new() });
+}
+
+/// Give each layer a bit, and make its mask from the layers it collides with.
+/// Collisions go both ways, so each layer also collides with any layer that
+/// lists it.
+fn build_filters(defs: &BTreeMap<String, CollLayerDef>) -> Vec<(String, CollFilter)> {
+    assert!(defs.len() <= MAX_COLL_LAYERS,
+            "Too many collision layers ({}, max is {})", defs.len(), MAX_COLL_LAYERS);
+    let mut filters : Vec<(String, CollFilter)> = defs.keys().enumerate()
+        .map(|(ix, k)| (k.clone(), CollFilter { layer: 1 << ix, mask: 0 })).collect();
+    for (ix, def) in defs.values().enumerate() {
+        for other in &def.collides_with {
+            let other_ix = defs.keys().position(|k| k == other)
+                .expect(&format!("Unknown collision layer {}", other));
+            filters[ix].1.mask |= 1 << other_ix;
+            filters[other_ix].1.mask |= 1 << ix;
+        }
+    }
+    filters
+}
+
+/// Get the collision filter for colliders on the layer with the given name.
+/// Panics if there's no such layer.
+pub fn get_coll_filter_with_name(name: &str) -> CollFilter {
+    COLL_LAYER_REGISTER.read().unwrap().layers.iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, f)| *f)
+        .expect(&format!("Unknown collision layer {}", name))
+}
+
+fn load_defs() -> BTreeMap<String, CollLayerDef> {
+    let mut f = fs::File::open("res/collision-layers.yaml").unwrap();
+    serde_yaml::from_reader(&mut f).unwrap()
+}
+
+/// Load the collision layers from the res files
+pub fn load_coll_layers() {
+    let filters = build_filters(&load_defs());
+    COLL_LAYER_REGISTER.write().unwrap().layers = filters;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def(collides_with: &[&str]) -> CollLayerDef {
+        CollLayerDef { collides_with: collides_with.iter().map(|s| s.to_string()).collect() }
+    }
+
+    #[test]
+    fn layers_collide_both_ways() {
+        let mut defs = BTreeMap::new();
+        defs.insert("A".to_string(), def(&["B"]));
+        defs.insert("B".to_string(), def(&[]));
+        defs.insert("C".to_string(), def(&["C"]));
+        let filters = build_filters(&defs);
+        let (a, b, c) = (filters[0].1, filters[1].1, filters[2].1);
+        assert!(a.collides(&b) && b.collides(&a));
+        assert!(!a.collides(&a) && !b.collides(&b));
+        assert!(c.collides(&c));
+        assert!(!a.collides(&c) && !c.collides(&b));
+    }
+
+    #[test]
+    fn coll_layer_file_parses() {
+        let filters = build_filters(&load_defs());
+        for name in &["Terrain", "Character", "Hitbox", "Projectile", "Item"] {
+            assert!(filters.iter().any(|(n, _)| n == name), "Missing layer {}", name);
+        }
+    }
+}
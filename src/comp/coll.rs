@@ -1,5 +1,5 @@
 use vec::*;
-use specs::{DenseVecStorage};
+use specs::{DenseVecStorage, NullStorage};
 
 pub trait Coll<C> {
     /// Try and resolve this collision by shifting us some vector. Assume the
@@ -7,23 +7,126 @@ pub trait Coll<C> {
     /// This won't be called if the flags has COLL_STATIC.
     fn resolve(&self, other: &C, self_pos: Vec32, other_pos: Vec32) -> Vec32;
 
+    /// Sweep this collider from `from` to `to`, and get how far along the way
+    /// (0 to 1) it first touches the other collider. Returns None if it
+    /// doesn't, or if they already overlap at `from` (resolve deals with that).
+    fn sweep(&self, other: &C, from: Vec32, to: Vec32, other_pos: Vec32) -> Option<f32>;
+
     /// Get the AABB bounding box for this collision shape (i.e. top left, bottom right point)
     fn aabb(&self, pos: Vec32) -> [Vec32; 2];
 
     /// Return this collision object's flags
     fn flags(&self) -> u8;
+
+    /// Return this collision object's layer & mask
+    fn filter(&self) -> CollFilter;
+}
+
+/// Which collision layer a collider is on, and which layers it collides with.
+/// Each layer is a bit. Filters for the layers in res/collision-layers.yaml can
+/// be got with `coll_layers::get_coll_filter_with_name`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CollFilter {
+    /// The layer bit of this collider
+    pub layer: u32,
+    /// The layer bits this collider collides with
+    pub mask: u32,
+}
+
+impl CollFilter {
+    /// A filter on every layer, which collides with everything
+    pub fn all() -> CollFilter {
+        CollFilter { layer: !0, mask: !0 }
+    }
+
+    /// Should colliders with these filters be tested against each other? Both
+    /// have to accept the other's layer.
+    pub fn collides(&self, other: &CollFilter) -> bool {
+        self.mask & other.layer > 0 && other.mask & self.layer > 0
+    }
+}
+
+/// How tall a collider is. It spans from its Pos's z up to z + h, and only
+/// collides with colliders whose z range overlaps it, so airborne entities can
+/// pass over low obstacles. Colliders without this span every height.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct CollHeight {
+    pub h: f32,
+}
+
+impl CollHeight {
+    /// Do 2 colliders at the given heights overlap along z?
+    pub fn overlaps(z0: f32, h0: Option<&CollHeight>, z1: f32, h1: Option<&CollHeight>) -> bool {
+        match (h0, h1) {
+            (Some(h0), Some(h1)) => z0 < z1 + h1.h && z1 < z0 + h0.h,
+            _ => true,
+        }
+    }
 }
 
 /// Does this collision body affect the physics of entities?
 pub const COLL_SOLID : u8 = 1;
 /// Is this body moved by other solid bodies? (assumes COLL_SOLID = 1)
 pub const COLL_STATIC : u8 = 2;
+/// Use continuous collision detection, so this body can't pass through static
+/// bodies or solid tiles when moving more than half its size in a tick.
+/// (assumes COLL_SOLID = 1)
+pub const COLL_CCD : u8 = 4;
+
+/// Bodies with COLL_CCD only sweep when they move further than this in a tick,
+/// which is half the smallest side of their AABB.
+pub fn ccd_threshold(aabb: [Vec32; 2]) -> f32 {
+    (aabb[1].x - aabb[0].x).min(aabb[1].y - aabb[0].y) / 2.0
+}
+
+/// How far past the first contact CCD stops a body
+const CCD_OVERLAP : f32 = 0.1;
+
+/// Where a body sweeping from `from` to `to` stops when it first touches
+/// something `toi` (0 to 1) of the way along. This is slightly past touching,
+/// so the bodies overlap and resolving them records the collision and bounces.
+pub fn ccd_stop(from: Vec32, to: Vec32, toi: f32) -> Vec32 {
+    let len = (to - from).len();
+    if len == 0.0 { return to }
+    from + (to - from) * (toi + CCD_OVERLAP / len).min(1.0)
+}
+
+/// How far along a ray (0 to 1) from `from`, moving `d`, it enters the AABB.
+/// None if it misses, or starts inside.
+fn ray_aabb(from: Vec32, d: Vec32, min: Vec32, max: Vec32) -> Option<f32> {
+    let (mut t_min, mut t_max) = (0.0f32, 1.0f32);
+    let mut starts_inside = true;
+    for &(o, d, min, max) in &[(from.x, d.x, min.x, max.x), (from.y, d.y, min.y, max.y)] {
+        if o <= min || o >= max { starts_inside = false; }
+        if d == 0.0 {
+            if o <= min || o >= max { return None }
+        } else {
+            let (t0, t1) = ((min - o) / d, (max - o) / d);
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+        }
+    }
+    if starts_inside || t_min >= t_max { None } else { Some(t_min) }
+}
+
+/// How far along a ray (0 to 1) from `from`, moving `d`, it enters the circle.
+/// None if it misses, or starts inside.
+fn ray_circle(from: Vec32, d: Vec32, centre: Vec32, r: f32) -> Option<f32> {
+    let m = from - centre;
+    let (a, b, c) = (d.dot(d), m.dot(d), m.dot(m) - r * r);
+    if c <= 0.0 || b >= 0.0 || a == 0.0 { return None }
+    let disc = b * b - a * c;
+    if disc < 0.0 { return None }
+    let t = (-b - disc.sqrt()) / a;
+    if t > 1.0 { None } else { Some(t) }
+}
 
 #[derive(Component)]
 pub struct CollCircle {
     pub r: f32,
     pub off: Vec32,
     pub flags: u8,
+    pub filter: CollFilter,
 }
 
 impl Coll<CollCircle> for CollCircle {
@@ -42,10 +145,286 @@ impl Coll<CollCircle> for CollCircle {
         }
     }
 
+    fn sweep(&self, other: &CollCircle, from: Vec32, to: Vec32, other_pos: Vec32) -> Option<f32> {
+        ray_circle(from + self.off, to - from, other_pos + other.off, self.r + other.r)
+    }
+
+    fn aabb(&self, pos: Vec32) -> [Vec32; 2] {
+        let centre = pos + self.off;
+        [Vec32::new(centre.x - self.r, centre.y - self.r),
+         Vec32::new(centre.x + self.r, centre.y + self.r)]
+    }
+
+    fn flags(&self) -> u8 { self.flags }
+
+    fn filter(&self) -> CollFilter { self.filter }
+}
+
+/// Marks a collider as a trigger volume. TriggerSys publishes events when
+/// other colliders start or stop overlapping it. Triggers should usually not
+/// have COLL_SOLID set.
+#[derive(Clone, Copy, Default, Component)]
+#[storage(NullStorage)]
+pub struct Trigger;
+
+/// An axis-aligned rectangle, centred on the entity's position plus the offset
+#[derive(Component)]
+pub struct CollRect {
+    pub w: f32,
+    pub h: f32,
+    pub off: Vec32,
+    pub flags: u8,
+    pub filter: CollFilter,
+}
+
+impl Coll<CollRect> for CollRect {
+    fn resolve(&self, other: &CollRect, self_pos: Vec32, other_pos: Vec32) -> Vec32 {
+        let vec = (self_pos + self.off) - (other_pos + other.off);
+        let overlap_x = (self.w + other.w) / 2.0 - vec.x.abs();
+        let overlap_y = (self.h + other.h) / 2.0 - vec.y.abs();
+        if overlap_x <= 0.0 || overlap_y <= 0.0 {
+            Vec32::new(0.0, 0.0)
+        } else if overlap_x < overlap_y {
+            Vec32::new(if vec.x < 0.0 { -overlap_x } else { overlap_x }, 0.0)
+        } else {
+            Vec32::new(0.0, if vec.y < 0.0 { -overlap_y } else { overlap_y })
+        }
+    }
+
+    fn sweep(&self, other: &CollRect, from: Vec32, to: Vec32, other_pos: Vec32) -> Option<f32> {
+        // Sweep our centre against the other rect grown by our size
+        let half = Vec32::new(self.w, self.h) / 2.0;
+        let [min, max] = <CollRect as Coll<CollRect>>::aabb(other, other_pos);
+        ray_aabb(from + self.off, to - from, min - half, max + half)
+    }
+
+    fn aabb(&self, pos: Vec32) -> [Vec32; 2] {
+        let centre = pos + self.off;
+        [Vec32::new(centre.x - self.w / 2.0, centre.y - self.h / 2.0),
+         Vec32::new(centre.x + self.w / 2.0, centre.y + self.h / 2.0)]
+    }
+
+    fn flags(&self) -> u8 { self.flags }
+
+    fn filter(&self) -> CollFilter { self.filter }
+}
+
+impl Coll<CollRect> for CollCircle {
+    fn resolve(&self, other: &CollRect, self_pos: Vec32, other_pos: Vec32) -> Vec32 {
+        let centre = self_pos + self.off;
+        let [min, max] = <CollRect as Coll<CollRect>>::aabb(other, other_pos);
+        let closest = Vec32::new(centre.x.max(min.x).min(max.x),
+                                 centre.y.max(min.y).min(max.y));
+        let vec = centre - closest;
+        let dis = vec.len();
+        if dis >= self.r {
+            Vec32::new(0.0, 0.0)
+        } else if dis > 0.0 {
+            vec.nor() * (self.r - dis)
+        } else {
+            // The centre is inside the rect, so push out through the closest
+            // side
+            let sides = [(centre.x - min.x, Vec32::new(-1.0, 0.0)),
+                         (max.x - centre.x, Vec32::new(1.0, 0.0)),
+                         (centre.y - min.y, Vec32::new(0.0, -1.0)),
+                         (max.y - centre.y, Vec32::new(0.0, 1.0))];
+            let &(d, dir) = sides.iter()
+                .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap()).unwrap();
+            dir * (d + self.r)
+        }
+    }
+
+    fn sweep(&self, other: &CollRect, from: Vec32, to: Vec32, other_pos: Vec32) -> Option<f32> {
+        if self.resolve(other, from, other_pos).len() > 0.0 { return None }
+        // Sweep our centre against the rect grown by our radius, which is made
+        // of 2 crossed rects and a circle at each corner
+        let (from, d, r) = (from + self.off, to - from, self.r);
+        let [min, max] = <CollRect as Coll<CollRect>>::aabb(other, other_pos);
+        let corners = [min, max, Vec32::new(min.x, max.y), Vec32::new(max.x, min.y)];
+        corners.iter().filter_map(|&c| ray_circle(from, d, c, r))
+            .chain(ray_aabb(from, d, min - Vec32::new(r, 0.0), max + Vec32::new(r, 0.0)))
+            .chain(ray_aabb(from, d, min - Vec32::new(0.0, r), max + Vec32::new(0.0, r)))
+            .fold(None, |best, t| Some(best.map_or(t, |b: f32| b.min(t))))
+    }
+
+    fn aabb(&self, pos: Vec32) -> [Vec32; 2] {
+        <CollCircle as Coll<CollCircle>>::aabb(self, pos)
+    }
+
+    fn flags(&self) -> u8 { self.flags }
+
+    fn filter(&self) -> CollFilter { self.filter }
+}
+
+impl Coll<CollCircle> for CollRect {
+    fn resolve(&self, other: &CollCircle, self_pos: Vec32, other_pos: Vec32) -> Vec32 {
+        -other.resolve(self, other_pos, self_pos)
+    }
+
+    fn sweep(&self, other: &CollCircle, from: Vec32, to: Vec32, other_pos: Vec32) -> Option<f32> {
+        // Same as the circle moving the opposite way past us
+        other.sweep(self, other_pos, other_pos - (to - from), from)
+    }
+
     fn aabb(&self, pos: Vec32) -> [Vec32; 2] {
-        [Vec32::new(pos.x - self.off.x, pos.y - self.off.y),
-         Vec32::new(pos.x + self.off.x, pos.y + self.off.y)]
+        <CollRect as Coll<CollRect>>::aabb(self, pos)
     }
 
     fn flags(&self) -> u8 { self.flags }
+
+    fn filter(&self) -> CollFilter { self.filter }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(r: f32) -> CollCircle {
+        CollCircle { r: r, off: Vec32::zero(), flags: COLL_SOLID,
+                     filter: CollFilter::all() }
+    }
+
+    fn rect(w: f32, h: f32) -> CollRect {
+        CollRect { w: w, h: h, off: Vec32::zero(), flags: COLL_SOLID,
+                   filter: CollFilter::all() }
+    }
+
+    #[test]
+    fn coll_height_overlap() {
+        let low = CollHeight { h: 12.0 };
+        let slime = CollHeight { h: 16.0 };
+        assert!(CollHeight::overlaps(0.0, Some(&slime), 0.0, Some(&low)));
+        assert!(CollHeight::overlaps(11.0, Some(&slime), 0.0, Some(&low)));
+        assert!(!CollHeight::overlaps(12.0, Some(&slime), 0.0, Some(&low)));
+        // Without a height, colliders span every height
+        assert!(CollHeight::overlaps(100.0, Some(&slime), 0.0, None));
+        assert!(CollHeight::overlaps(100.0, None, 0.0, Some(&low)));
+    }
+
+    fn assert_near(a: Vec32, b: Vec32) {
+        assert!((a - b).len() < 0.001, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn rect_rect_resolves_along_smallest_overlap() {
+        let (a, b) = (rect(20.0, 20.0), rect(40.0, 10.0));
+        // Overlapping by 5 on x and 10 on y, with a to the right of b
+        let res = a.resolve(&b, Vec32::new(25.0, 2.0), Vec32::zero());
+        assert_near(res, Vec32::new(5.0, 0.0));
+        assert_near(res.nor(), Vec32::new(1.0, 0.0));
+        // Overlapping by 20 on x and 3 on y, with a above b
+        let res = a.resolve(&b, Vec32::new(10.0, -12.0), Vec32::zero());
+        assert_near(res, Vec32::new(0.0, -3.0));
+        // Just touching isn't a collision
+        let res = a.resolve(&b, Vec32::new(30.0, 0.0), Vec32::zero());
+        assert_near(res, Vec32::zero());
+    }
+
+    #[test]
+    fn circle_rect_resolves_from_side_and_corner() {
+        let (c, r) = (circle(10.0), rect(20.0, 20.0));
+        // Overlapping the left side by 4
+        let res = c.resolve(&r, Vec32::new(-16.0, 3.0), Vec32::zero());
+        assert_near(res, Vec32::new(-4.0, 0.0));
+        // Near the bottom right corner at (10, 10)
+        let pos = Vec32::new(13.0, 14.0);
+        let res = c.resolve(&r, pos, Vec32::zero());
+        assert_near(res.nor(), Vec32::new(0.6, 0.8));
+        assert!(((pos + res - Vec32::new(10.0, 10.0)).len() - 10.0).abs() < 0.001);
+        // Clear of the corner
+        let res = c.resolve(&r, Vec32::new(18.0, 18.0), Vec32::zero());
+        assert_near(res, Vec32::zero());
+    }
+
+    #[test]
+    fn circle_inside_rect_is_pushed_through_nearest_side() {
+        let (c, r) = (circle(10.0), rect(20.0, 20.0));
+        let res = c.resolve(&r, Vec32::new(2.0, 8.0), Vec32::zero());
+        assert_near(res, Vec32::new(0.0, 12.0));
+    }
+
+    #[test]
+    fn rect_circle_is_opposite_of_circle_rect() {
+        let (c, r) = (circle(10.0), rect(20.0, 20.0));
+        let (c_pos, r_pos) = (Vec32::new(-16.0, 3.0), Vec32::new(1.0, 1.0));
+        let res = r.resolve(&c, r_pos, c_pos);
+        assert_near(res, -c.resolve(&r, c_pos, r_pos));
+        assert!(res.x > 0.0);
+    }
+
+    #[test]
+    fn sweeps_find_first_contact() {
+        let (c, r) = (circle(10.0), rect(20.0, 20.0));
+        let (from, to) = (Vec32::new(-100.0, 0.0), Vec32::new(100.0, 0.0));
+        // Circle - circle touches when the centres are 20 apart, at x = -20
+        let t = c.sweep(&circle(10.0), from, to, Vec32::zero()).unwrap();
+        assert!((t - 0.4).abs() < 0.001, "{}", t);
+        // Circle - rect touches at x = -20
+        let t = c.sweep(&r, from, to, Vec32::zero()).unwrap();
+        assert!((t - 0.4).abs() < 0.001, "{}", t);
+        // Rect - rect touches at x = -20
+        let t = r.sweep(&r, from, to, Vec32::zero()).unwrap();
+        assert!((t - 0.4).abs() < 0.001, "{}", t);
+        // Rect - circle touches at x = -20
+        let t = r.sweep(&c, from, to, Vec32::zero()).unwrap();
+        assert!((t - 0.4).abs() < 0.001, "{}", t);
+    }
+
+    #[test]
+    fn circle_rect_sweep_uses_rounded_corners() {
+        let (c, r) = (circle(10.0), rect(20.0, 20.0));
+        // Passing the corner at (10, 10) diagonally misses the rounded corner
+        // but would hit a square one
+        let (from, to) = (Vec32::new(47.0, -10.0), Vec32::new(-10.0, 47.0));
+        assert_eq!(c.sweep(&r, from, to, Vec32::zero()), None);
+        assert!(r.sweep(&rect(20.0, 20.0), from, to, Vec32::zero()).is_some());
+        // Hitting the corner head on
+        let (from, to) = (Vec32::new(30.0, 30.0), Vec32::new(0.0, 0.0));
+        let t = c.sweep(&r, from, to, Vec32::zero()).unwrap();
+        let contact = from + (to - from) * t;
+        assert!(((contact - Vec32::new(10.0, 10.0)).len() - 10.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn ccd_stop_overlaps_so_resolve_runs() {
+        let (c, r) = (circle(10.0), rect(20.0, 20.0));
+        let (from, to) = (Vec32::new(-100.0, 0.0), Vec32::new(100.0, 0.0));
+        let stop = ccd_stop(from, to, c.sweep(&r, from, to, Vec32::zero()).unwrap());
+        assert!(stop.x > -20.0 && stop.x < -19.0, "{:?}", stop);
+        assert!(c.resolve(&r, stop, Vec32::zero()).x < 0.0);
+        let stop = ccd_stop(from, to, r.sweep(&r, from, to, Vec32::zero()).unwrap());
+        assert!(r.resolve(&r, stop, Vec32::zero()).x < 0.0);
+        // Never goes past the end
+        assert_eq!(ccd_stop(from, to, 1.0), to);
+    }
+
+    #[test]
+    fn sweeps_miss_and_ignore_overlaps() {
+        let (c, r) = (circle(10.0), rect(20.0, 20.0));
+        // Stops short
+        assert_eq!(c.sweep(&r, Vec32::new(-100.0, 0.0), Vec32::new(-50.0, 0.0),
+                           Vec32::zero()), None);
+        // Passes by
+        assert_eq!(c.sweep(&circle(10.0), Vec32::new(-100.0, 30.0), Vec32::new(100.0, 30.0),
+                           Vec32::zero()), None);
+        // Already overlapping
+        assert_eq!(c.sweep(&r, Vec32::new(-15.0, 0.0), Vec32::new(100.0, 0.0),
+                           Vec32::zero()), None);
+        assert_eq!(r.sweep(&r, Vec32::new(-15.0, 0.0), Vec32::new(100.0, 0.0),
+                           Vec32::zero()), None);
+    }
+
+    #[test]
+    fn aabbs_use_size_and_offset() {
+        let c = CollCircle { r: 5.0, off: Vec32::new(1.0, 2.0), flags: 0,
+                             filter: CollFilter::all() };
+        let aabb = Coll::<CollCircle>::aabb(&c, Vec32::new(10.0, 10.0));
+        assert_near(aabb[0], Vec32::new(6.0, 7.0));
+        assert_near(aabb[1], Vec32::new(16.0, 17.0));
+        let r = CollRect { w: 4.0, h: 6.0, off: Vec32::new(1.0, 0.0), flags: 0,
+                           filter: CollFilter::all() };
+        let aabb = Coll::<CollRect>::aabb(&r, Vec32::zero());
+        assert_near(aabb[0], Vec32::new(-1.0, -3.0));
+        assert_near(aabb[1], Vec32::new(3.0, 3.0));
+    }
 }
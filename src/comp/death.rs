@@ -2,6 +2,8 @@
 
 use drop_tables::*;
 use specs::*;
+use renderer::TextureKey;
+use enemies::EnemyType;
 
 /// For entities that drop something on death (i.e. on entity deletion,
 /// regardless of how it happened, for example if this is added to a projectile
@@ -20,3 +22,53 @@ pub struct OnDeathDrop {
     /// Maxmimum number of drops to process (Exclusive)
     pub max_drops: u8,
 }
+
+/// Something spawned by OnImpact
+#[derive(Clone, Copy, Debug)]
+#[allow(dead_code)]
+pub enum ImpactEffect {
+    /// A one-shot animation, removed once it has played
+    Anim {
+        anim: TextureKey,
+        w: f32,
+        h: f32,
+        num_frames: usize,
+        /// Time per frame in millis
+        frame_time: f32,
+    },
+    /// An enemy, e.g. a slime hatching from a thrown egg
+    Enemy(EnemyType),
+}
+
+/// Spawns an effect where this entity is killed, or where a projectile or
+/// HURT_DIES hurt is used up. Like OnDeathDrop, this needs a Pos.
+#[derive(Component)]
+pub struct OnImpact {
+    pub effect: ImpactEffect,
+}
+
+/// Entities with this aren't deleted when they're killed. Instead they're Dead
+/// for a while, then come back to life at the SpawnPoint with full health.
+#[derive(Component)]
+pub struct Respawn {
+    /// How long the entity stays dead for, in millis
+    pub delay: f32,
+}
+
+/// A killed entity waiting to respawn. Dead entities can't be hurt, aren't
+/// controlled, aren't targeted by enemies, and their CollCircle doesn't collide
+/// with anything.
+#[derive(Component)]
+pub struct Dead {
+    /// Millis until respawning. Counts to 0, then this is removed.
+    pub time: f32,
+    /// The mask of the entity's CollCircle before it died, which is put back
+    /// when it respawns
+    pub coll_mask: Option<u32>,
+}
+
+/// Triggers with this move the SpawnPoint to themselves when the player
+/// enters them.
+#[derive(Component)]
+#[allow(dead_code)]
+pub struct Checkpoint;
@@ -51,13 +51,50 @@ impl Hitmask {
     }
 }
 
+/// A type of damage. Each type is resisted separately.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
+pub enum DamageType {
+    Physical,
+    Fire,
+    Cold,
+    Poison,
+}
+
+pub const NUM_DAMAGE_TYPES : usize = 4;
+
+/// How much of each type of damage an entity resists, in percent. 100 (or
+/// more) is immune, and negative values are weaknesses which take extra
+/// damage.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub struct Resistances(pub [i32; NUM_DAMAGE_TYPES]);
+
+impl Resistances {
+    pub fn get(&self, ty: DamageType) -> i32 {
+        self.0[ty as usize]
+    }
+
+    pub fn set(&mut self, ty: DamageType, percent: i32) {
+        self.0[ty as usize] = percent;
+    }
+
+    /// Get the damage taken from a hit of the given type. Any hit that isn't
+    /// fully resisted does at least 1 damage.
+    pub fn apply(&self, damage: u32, ty: DamageType) -> u32 {
+        let resistance = self.get(ty);
+        if damage == 0 || resistance >= 100 { return 0 }
+        let damage = damage as u64 * (100 - resistance as i64) as u64 / 100;
+        damage.max(1).min(u32::max_value() as u64) as u32
+    }
+}
+
 /// Comp that indicates this entity has health, which can be removed via
 /// combat.. Once health is reduced to 0, the entity will be removed. This also
 /// contains the entity's stats, such as resistances.
 #[derive(Component)]
 pub struct Health {
-    pub max_health: u8,
-    pub health: u8,
+    pub max_health: u32,
+    pub health: u32,
     /// Maximum invuln time after being hit (in millis)
     pub max_inv_time: f32,
     /// Counts to 0
@@ -68,35 +105,58 @@ pub struct Health {
     /// This will be hit by hurtboxes which have HITMASK_ALLY as part of their
     /// hitmask.
     pub mask: Hitmask,
+    pub resistances: Resistances,
 }
 
 impl Health {
-    pub fn new(max_health: u8, mask: Hitmask) -> Health {
+    pub fn new(max_health: u32, mask: Hitmask) -> Health {
         Health {
             max_health: max_health,
             health: max_health,
             mask: mask,
             max_inv_time: 300.0,
             inv_time: 0.0,
+            resistances: Resistances::default(),
         }
     }
 
-    /// Hurt this health component with the hurt component. Returns true if this
-    /// entity should die now.
-    pub fn hurt(&mut self, hurt: &Hurt) -> bool {
-        if self.health > hurt.damage {
-            self.health -= hurt.damage;
-            false
+    /// Set the resistance to a type of damage
+    pub fn with_resistance(mut self, ty: DamageType, percent: i32) -> Health {
+        self.resistances.set(ty, percent);
+        self
+    }
+
+    /// Hurt this health component with the hurt component. Returns the damage
+    /// dealt (after resistances), and true if this entity should die now.
+    pub fn hurt(&mut self, hurt: &Hurt) -> (u32, bool) {
+        self.take_damage(hurt.damage, hurt.damage_type)
+    }
+
+    /// Take damage of a given type (before resistances). Returns the damage
+    /// dealt (after resistances), and true if this entity should die now.
+    pub fn take_damage(&mut self, damage: u32, damage_type: DamageType) -> (u32, bool) {
+        let damage = self.resistances.apply(damage, damage_type);
+        if self.health > damage {
+            self.health -= damage;
+            (damage, false)
         } else {
             self.health = 0;
-            true
+            (damage, true)
         }
     }
+
+    /// Heal by some amount, up to the max health. Returns how much was
+    /// actually healed.
+    pub fn heal(&mut self, amount: u32) -> u32 {
+        let old = self.health;
+        self.health = self.health.saturating_add(amount).min(self.max_health);
+        self.health - old
+    }
 }
 
-/// If this is set, the hurt component will be removed once it hurts one thing.
-/// This is useful for projectile attacks.
-#[allow(dead_code)]
+/// If this is set, the hurt entity will be destroyed once it hurts one thing
+/// (or, for a Projectile, once it runs out of pierce). This is useful for
+/// projectile attacks.
 pub const HURT_DIES : u8 = 1;
 
 /// If an entity contains this, this means that if it collides with another
@@ -104,7 +164,8 @@ pub const HURT_DIES : u8 = 1;
 #[derive(Component)]
 pub struct Hurt {
     /// How much damage to inflict (before resistances)
-    pub damage: u8,
+    pub damage: u32,
+    pub damage_type: DamageType,
     /// What does this hurt?
     pub mask: Hitmask,
     /// Some flags. See the HURT_* consts.
@@ -115,18 +176,73 @@ pub struct Hurt {
 /// entity that has health, it will knock that entity back in a given direction
 #[derive(Component)]
 pub struct HurtKnockbackDir {
+    /// Impulse to knock the other entity back with. This is the change in
+    /// velocity for an entity with a mass of 1.
     pub knockback: Vec32,
-    /// Duration in millis. Counts to 0, when 0, removes this component.
+    /// How long the other entity is knocked back for, in millis.
     pub duration: f32,
 }
 
-/// Knockback will apply the given velocity to an object until the duration
-/// wears off. Vel will apply over 1 second - so if this contains (100, 50)
-/// and the OnHit duration is 1000.0, this will knock the entity back
-/// roughly 100, 50 (but this shouldn't be relied on due to rouding errs)
+/// Knockback gives an entity an impulse, which KnockbackSys adds to its Vel
+/// (so Damping slows it down again). Controllers don't steer the entity until
+/// the duration wears off.
 #[derive(Component)]
 pub struct Knockback {
+    /// Change in velocity. This is zeroed once it's been added to the Vel.
     pub knockback: Vec32,
     /// Duration in millis. Counts to 0, when 0, removes this component.
     pub duration: f32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hurt(damage: u32, damage_type: DamageType) -> Hurt {
+        Hurt { damage, damage_type, mask: Hitmask(HITMASK_ENEMY), flags: 0 }
+    }
+
+    /// Get how much damage a hurt would do to some health
+    fn damage_from(health: &Health, hurt: &Hurt) -> u32 {
+        health.resistances.apply(hurt.damage, hurt.damage_type)
+    }
+
+    #[test]
+    fn resistances_scale_damage() {
+        let health = Health::new(100, Hitmask(HITMASK_ENEMY))
+            .with_resistance(DamageType::Fire, 50)
+            .with_resistance(DamageType::Cold, -50)
+            .with_resistance(DamageType::Poison, 100);
+        assert_eq!(damage_from(&health, &hurt(10, DamageType::Physical)), 10);
+        assert_eq!(damage_from(&health, &hurt(10, DamageType::Fire)), 5);
+        assert_eq!(damage_from(&health, &hurt(10, DamageType::Cold)), 15);
+        assert_eq!(damage_from(&health, &hurt(10, DamageType::Poison)), 0);
+        // Resisted hits still do something
+        assert_eq!(damage_from(&health, &hurt(1, DamageType::Fire)), 1);
+        assert_eq!(damage_from(&health, &hurt(0, DamageType::Physical)), 0);
+    }
+
+    #[test]
+    fn large_numbers() {
+        let mut health = Health::new(3_000_000_000, Hitmask(HITMASK_ENEMY))
+            .with_resistance(DamageType::Physical, -200);
+        assert_eq!(damage_from(&health, &hurt(2_000_000_000, DamageType::Physical)),
+                   u32::max_value());
+        assert_eq!(health.hurt(&hurt(500_000_000, DamageType::Physical)),
+                   (1_500_000_000, false));
+        assert_eq!(health.health, 1_500_000_000);
+        assert_eq!(health.hurt(&hurt(500_000_000, DamageType::Physical)),
+                   (1_500_000_000, true));
+        assert_eq!(health.health, 0);
+    }
+
+    #[test]
+    fn heal_up_to_max() {
+        let mut health = Health::new(8, Hitmask(HITMASK_PLAYER));
+        health.health = 3;
+        assert_eq!(health.heal(2), 2);
+        assert_eq!(health.health, 5);
+        assert_eq!(health.heal(u32::max_value()), 3);
+        assert_eq!(health.health, 8);
+    }
+}
@@ -1,18 +1,26 @@
 //! Components for the ECS
 
 mod coll;
+mod phys;
 mod control;
 mod health;
 mod visual;
 mod alliance;
 mod death;
+mod projectile;
+mod stats;
+mod status;
 
 pub use self::coll::*;
+pub use self::phys::*;
 pub use self::control::*;
 pub use self::health::*;
 pub use self::visual::*;
 pub use self::alliance::*;
 pub use self::death::*;
+pub use self::projectile::*;
+pub use self::stats::*;
+pub use self::status::*;
 use inventory::InventoryItem;
 use vec::*;
 use specs::*;
@@ -44,6 +52,31 @@ pub struct Pos {
     pub z: f32,
 }
 
+impl Pos {
+    /// Get the position to draw this at, given where it was last tick and how
+    /// far (0 to 1) we are through the current tick. Entities without a
+    /// previous position (i.e. created this tick) aren't interpolated.
+    pub fn interpolate(&self, prev: Option<&PrevPos>, alpha: f32) -> Pos {
+        match prev {
+            Some(prev) => Pos {
+                pos: prev.pos + (self.pos - prev.pos) * alpha,
+                z: prev.z + (self.z - prev.z) * alpha,
+            },
+            None => self.clone(),
+        }
+    }
+}
+
+/// The Pos of this entity at the start of the current tick, so rendering can
+/// interpolate between ticks. This is added to everything with a Pos by
+/// PrevPosSys. Remove it when teleporting an entity, so it isn't drawn
+/// sliding to its new position.
+#[derive(Clone, Component)]
+pub struct PrevPos {
+    pub pos: Vec32,
+    pub z: f32,
+}
+
 #[derive(Component)]
 pub struct Vel {
     pub vel: Vec32,
@@ -75,11 +108,19 @@ pub struct Collector {
 }
 
 /// Equipment which should be drawn on a character and affect their stats in
-/// battle
-#[derive(Component, Default)]
+/// battle (if they have Stats)
+#[derive(Component, Default, Clone, PartialEq, Debug)]
 pub struct Equipment {
     pub helmet: Option<ItemType>,
     pub body: Option<ItemType>,
     pub weapon: Option<ItemType>,
     pub ring: Option<ItemType>,
 }
+
+impl Equipment {
+    /// Get all the equipped items
+    pub fn items(&self) -> Vec<ItemType> {
+        [self.helmet, self.body, self.weapon, self.ring].iter()
+            .filter_map(|item| *item).collect()
+    }
+}
@@ -0,0 +1,186 @@
+use specs::DenseVecStorage;
+use vec::*;
+
+/// Mass of entities without a Mass component
+pub const DEFAULT_MASS : f32 = 1.0;
+
+/// How heavy an entity is. When solid bodies collide, the lighter one is
+/// pushed further, and knockback moves heavy entities less. Entities without
+/// this have DEFAULT_MASS.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Mass {
+    pub mass: f32,
+    /// How bouncy this is, from 0 (stops dead when hitting something) to 1
+    /// (bounces off at full speed). If None, hitting something doesn't change
+    /// this entity's velocity at all.
+    pub restitution: Option<f32>,
+}
+
+impl Mass {
+    /// Get the mass of something that might have a Mass component
+    pub fn of(mass: Option<&Mass>) -> f32 {
+        mass.map_or(DEFAULT_MASS, |m| m.mass)
+    }
+
+    /// How much (0 to 1) of an overlap between 2 non-static bodies the first
+    /// body should be moved to resolve it
+    pub fn resolve_share(mass: Option<&Mass>, other: Option<&Mass>) -> f32 {
+        let (m0, m1) = (Mass::of(mass), Mass::of(other));
+        m1 / (m0 + m1)
+    }
+
+    /// Get the change in velocity from bouncing off another body. The normal
+    /// points from the other body to this one. If other_mass is None, the
+    /// other body is static.
+    pub fn bounce(&self, vel: Vec32, other_vel: Vec32, other_mass: Option<f32>,
+                  normal: Vec32) -> Vec32 {
+        let restitution = match self.restitution {
+            Some(r) => r,
+            None => return Vec32::zero(),
+        };
+        let approach = (vel - other_vel).dot(normal);
+        // Already moving apart
+        if approach >= 0.0 { return Vec32::zero() }
+        let inv_mass_sum = 1.0 / self.mass + other_mass.map_or(0.0, |m| 1.0 / m);
+        let impulse = -(1.0 + restitution) * approach / inv_mass_sum;
+        normal * (impulse / self.mass)
+    }
+}
+
+/// Slows an entity down over time. Applied by MoveSys every tick, so it's the
+/// same whatever the tick rate is.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Damping {
+    /// Drag in proportion to the speed - the velocity is multiplied by
+    /// e^(-drag) every second
+    pub drag: f32,
+    /// Constant deceleration in units per second per second
+    pub friction: f32,
+}
+
+impl Damping {
+    /// Get the velocity after dt seconds of damping. Friction never reverses
+    /// the velocity.
+    pub fn apply(&self, vel: Vec32, dt: f32) -> Vec32 {
+        let speed = vel.len();
+        if speed == 0.0 { return vel }
+        let damped = (speed * (-self.drag * dt).exp() - self.friction * dt).max(0.0);
+        vel * (damped / speed)
+    }
+}
+
+/// Below this speed, entities landing on the ground stop instead of bouncing
+pub const MIN_BOUNCE_SPEED : f32 = 20.0;
+
+/// Velocity along z (up is positive). MoveSys moves the entity's Pos z by this
+/// and pulls it down by gravity until it lands on the ground at z = 0.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct VelZ {
+    pub vel: f32,
+    /// Downwards acceleration in units per second per second
+    pub gravity: f32,
+    /// How much of its speed the entity keeps when it bounces off the ground,
+    /// from 0 (lands dead) to 1
+    pub bounce: f32,
+}
+
+impl VelZ {
+    /// Move a z height along by dt seconds
+    pub fn step(&mut self, z: &mut f32, dt: f32) {
+        if *z <= 0.0 && self.vel == 0.0 { return }
+        self.vel -= self.gravity * dt;
+        *z += self.vel * dt;
+        if *z <= 0.0 && self.vel < 0.0 {
+            *z = 0.0;
+            self.vel *= -self.bounce;
+            if self.vel < MIN_BOUNCE_SPEED { self.vel = 0.0; }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vec32, b: Vec32) {
+        assert!((a - b).len() < 0.001, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn equal_masses_share_resolution() {
+        assert_eq!(Mass::resolve_share(None, None), 0.5);
+        let heavy = Mass { mass: 3.0, restitution: None };
+        assert_eq!(Mass::resolve_share(Some(&heavy), None), 0.25);
+        assert_eq!(Mass::resolve_share(None, Some(&heavy)), 0.75);
+    }
+
+    #[test]
+    fn bounce_off_static_body() {
+        let normal = Vec32::new(-1.0, 0.0);
+        let vel = Vec32::new(100.0, 50.0);
+        let dead = Mass { mass: 2.0, restitution: Some(0.0) };
+        assert_near(vel + dead.bounce(vel, Vec32::zero(), None, normal),
+                    Vec32::new(0.0, 50.0));
+        let bouncy = Mass { mass: 2.0, restitution: Some(0.5) };
+        assert_near(vel + bouncy.bounce(vel, Vec32::zero(), None, normal),
+                    Vec32::new(-50.0, 50.0));
+        let no_bounce = Mass { mass: 2.0, restitution: None };
+        assert_near(no_bounce.bounce(vel, Vec32::zero(), None, normal), Vec32::zero());
+    }
+
+    #[test]
+    fn bounce_between_bodies_conserves_momentum() {
+        let (a, b) = (Mass { mass: 1.0, restitution: Some(1.0) },
+                      Mass { mass: 3.0, restitution: Some(1.0) });
+        let (va, vb) = (Vec32::new(10.0, 0.0), Vec32::new(-2.0, 0.0));
+        // Normal from b to a
+        let n = Vec32::new(-1.0, 0.0);
+        let dva = a.bounce(va, vb, Some(b.mass), n);
+        let dvb = b.bounce(vb, va, Some(a.mass), -n);
+        let before = va * a.mass + vb * b.mass;
+        let after = (va + dva) * a.mass + (vb + dvb) * b.mass;
+        assert_near(before, after);
+        // Moving apart now
+        assert!((va + dva - (vb + dvb)).dot(n) > 0.0);
+        // No change if already separating
+        assert_near(a.bounce(-va, vb, Some(b.mass), n), Vec32::zero());
+    }
+
+    #[test]
+    fn damping_is_tick_rate_independent() {
+        let damping = Damping { drag: 4.0, friction: 0.0 };
+        let vel = Vec32::new(300.0, -400.0);
+        let (mut slow, mut fast) = (vel, vel);
+        for _ in 0..30 { slow = damping.apply(slow, 1.0 / 30.0); }
+        for _ in 0..120 { fast = damping.apply(fast, 1.0 / 120.0); }
+        assert_near(slow, fast);
+        assert_near(slow, vel * (-4.0f32).exp());
+    }
+
+    #[test]
+    fn friction_stops_without_reversing() {
+        let damping = Damping { drag: 0.0, friction: 600.0 };
+        let vel = damping.apply(Vec32::new(0.0, 100.0), 0.1);
+        assert_near(vel, Vec32::new(0.0, 40.0));
+        assert_near(damping.apply(vel, 0.1), Vec32::zero());
+        assert_near(damping.apply(Vec32::zero(), 0.1), Vec32::zero());
+    }
+
+    #[test]
+    fn vel_z_bounces_then_lands() {
+        let mut vel_z = VelZ { vel: 200.0, gravity: 800.0, bounce: 0.5 };
+        let mut z = 0.0;
+        let (mut peak, mut bounces, mut was_falling) = (0.0f32, 0, false);
+        for _ in 0..600 {
+            vel_z.step(&mut z, 1.0 / 60.0);
+            assert!(z >= 0.0);
+            peak = peak.max(z);
+            if was_falling && vel_z.vel > 0.0 { bounces += 1; }
+            was_falling = vel_z.vel < 0.0;
+        }
+        // Peak of v^2 / 2g, give or take a tick
+        assert!((peak - 25.0).abs() < 4.0, "peak {}", peak);
+        assert!(bounces >= 1);
+        assert_eq!((z, vel_z.vel), (0.0, 0.0));
+    }
+}
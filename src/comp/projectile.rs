@@ -0,0 +1,65 @@
+//! Module for projectile components
+
+use specs::*;
+
+/// A hurt that flies at a constant speed in the direction of its Vel until it
+/// has gone `range` units, hits terrain, or (with VelZ) lands on the ground.
+/// If its Hurt has HURT_DIES, it can pierce through `pierce` entities after
+/// the first before it's used up. It never hits the same entity twice.
+#[derive(Component, Clone, Debug)]
+pub struct Projectile {
+    pub speed: f32,
+    pub range: f32,
+    pub pierce: u8,
+    /// How far this has flown so far
+    pub travelled: f32,
+    /// The entities this has already hit
+    pub hit: Vec<Entity>,
+}
+
+impl Projectile {
+    pub fn new(speed: f32, range: f32, pierce: u8) -> Projectile {
+        Projectile {
+            speed: speed,
+            range: range,
+            pierce: pierce,
+            travelled: 0.0,
+            hit: Vec::new(),
+        }
+    }
+
+    /// Has this already hit the given entity?
+    pub fn has_hit(&self, e: Entity) -> bool {
+        self.hit.contains(&e)
+    }
+
+    /// Record a hit on an entity. Returns true if this has no pierce left, so
+    /// it should be destroyed (if its Hurt has HURT_DIES).
+    pub fn hit(&mut self, e: Entity) -> bool {
+        self.hit.push(e);
+        if self.pierce == 0 { return true }
+        self.pierce -= 1;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pierce_runs_out() {
+        let mut world = World::new();
+        let (a, b, c) = (world.create_entity().build(), world.create_entity().build(),
+                         world.create_entity().build());
+        let mut projectile = Projectile::new(300.0, 200.0, 2);
+        assert!(!projectile.hit(a));
+        assert!(!projectile.hit(b));
+        assert!(projectile.hit(c));
+        assert!(projectile.has_hit(b));
+
+        let mut arrow = Projectile::new(300.0, 200.0, 0);
+        assert!(!arrow.has_hit(a));
+        assert!(arrow.hit(a));
+    }
+}
@@ -0,0 +1,114 @@
+//! Module for character stats, which equipment can modify
+
+use std::ops::Add;
+use specs::*;
+use super::*;
+
+/// A set of stats. This is used both for an entity's stats, and for the
+/// modifiers that a piece of equipment adds to them.
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatBlock {
+    pub max_health: u32,
+    /// Added to the physical resistance (in percent)
+    pub armour: i32,
+    /// Added to the damage of attacks
+    pub damage_bonus: u32,
+    pub move_speed: f32,
+}
+
+impl Add for StatBlock {
+    type Output = StatBlock;
+
+    fn add(self, other: StatBlock) -> StatBlock {
+        StatBlock {
+            max_health: self.max_health.saturating_add(other.max_health),
+            armour: self.armour + other.armour,
+            damage_bonus: self.damage_bonus.saturating_add(other.damage_bonus),
+            move_speed: self.move_speed + other.move_speed,
+        }
+    }
+}
+
+/// An entity's stats. StatsSys recalculates these from the base stats and the
+/// entity's Equipment whenever the equipment changes, and applies them to its
+/// Health and PlayerControlled.
+#[derive(Component)]
+pub struct Stats {
+    /// Stats without any equipment
+    pub base: StatBlock,
+    /// Base stats plus the equipment modifiers
+    pub current: StatBlock,
+    /// The equipment the current stats were calculated for. None if they
+    /// haven't been calculated yet.
+    pub equipment: Option<Equipment>,
+    /// The armour that's been added to the Health's physical resistance, so
+    /// it can be taken off again without touching its base resistance
+    applied_armour: i32,
+}
+
+impl Stats {
+    pub fn new(base: StatBlock) -> Stats {
+        Stats {
+            base: base,
+            current: base,
+            equipment: None,
+            applied_armour: 0,
+        }
+    }
+
+    /// Set the max health of a Health component to the current stats, and add
+    /// the current armour to its physical resistance (replacing any armour
+    /// added before). The health changes by as much as the max health does, so
+    /// the damage taken so far stays the same.
+    pub fn apply_to_health(&mut self, health: &mut Health) {
+        let damage_taken = health.max_health.saturating_sub(health.health);
+        health.max_health = self.current.max_health.max(1);
+        health.health = health.max_health.saturating_sub(damage_taken).max(1);
+        let base = health.resistances.get(DamageType::Physical) - self.applied_armour;
+        health.resistances.set(DamageType::Physical, base + self.current.armour);
+        self.applied_armour = self.current.armour;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_to_health_keeps_damage_taken() {
+        let mut stats = Stats::new(StatBlock { max_health: 8, armour: 0, damage_bonus: 0,
+                                               move_speed: 100.0 });
+        let mut health = Health::new(8, Hitmask(HITMASK_PLAYER));
+        health.health = 5;
+        stats.current = stats.base + StatBlock { max_health: 4, armour: 20,
+                                                 .. Default::default() };
+        stats.apply_to_health(&mut health);
+        assert_eq!((health.health, health.max_health), (9, 12));
+        assert_eq!(health.resistances.get(DamageType::Physical), 20);
+
+        // Taking the equipment off never kills
+        stats.current = stats.base;
+        health.health = 2;
+        stats.apply_to_health(&mut health);
+        assert_eq!((health.health, health.max_health), (1, 8));
+        assert_eq!(health.resistances.get(DamageType::Physical), 0);
+    }
+
+    #[test]
+    fn armour_adds_to_base_resistance() {
+        let mut stats = Stats::new(StatBlock { max_health: 8, armour: 0, damage_bonus: 0,
+                                               move_speed: 100.0 });
+        let mut health = Health::new(8, Hitmask(HITMASK_PLAYER))
+            .with_resistance(DamageType::Physical, 10);
+        stats.current = stats.base + StatBlock { armour: 20, .. Default::default() };
+        stats.apply_to_health(&mut health);
+        assert_eq!(health.resistances.get(DamageType::Physical), 30);
+        stats.current = stats.base + StatBlock { armour: 5, .. Default::default() };
+        stats.apply_to_health(&mut health);
+        assert_eq!(health.resistances.get(DamageType::Physical), 15);
+        stats.current = stats.base;
+        stats.apply_to_health(&mut health);
+        assert_eq!(health.resistances.get(DamageType::Physical), 10);
+    }
+}
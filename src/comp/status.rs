@@ -0,0 +1,176 @@
+//! Module for status effects (burning, poisoned etc)
+
+use specs::*;
+use super::*;
+
+/// Damage over time effects do their damage every this many millis
+pub const DOT_INTERVAL : f32 = 500.0;
+
+/// A kind of status effect
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
+pub enum StatusEffectType {
+    /// Takes `strength` fire damage every DOT_INTERVAL
+    Burning,
+    /// Takes `strength` poison damage per stack every DOT_INTERVAL
+    Poisoned,
+    /// Moves `strength` percent slower
+    Slowed,
+    /// Can't move or attack
+    Stunned,
+}
+
+/// What happens when an effect is applied to an entity that already has it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stacking {
+    /// The duration is reset, and the strongest strength is kept
+    Refresh,
+    /// Another stack is added (up to a maximum), and the duration is reset
+    Intensify { max_stacks: u32 },
+}
+
+impl StatusEffectType {
+    pub fn stacking(self) -> Stacking {
+        match self {
+            StatusEffectType::Poisoned => Stacking::Intensify { max_stacks: 5 },
+            _ => Stacking::Refresh,
+        }
+    }
+
+    /// Colour to tint entities with this effect
+    pub fn tint(self) -> [f32; 4] {
+        match self {
+            StatusEffectType::Burning => [1.0, 0.55, 0.2, 1.0],
+            StatusEffectType::Poisoned => [0.45, 1.0, 0.45, 1.0],
+            StatusEffectType::Slowed => [0.55, 0.7, 1.0, 1.0],
+            StatusEffectType::Stunned => [1.0, 1.0, 0.5, 1.0],
+        }
+    }
+}
+
+/// A timed status effect
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct StatusEffect {
+    #[serde(rename = "type")]
+    pub ty: StatusEffectType,
+    /// Time left in millis
+    pub duration: f32,
+    /// How strong this is. What this means depends on the type.
+    #[serde(default)]
+    pub strength: u32,
+    #[serde(skip, default = "one")]
+    pub stacks: u32,
+    /// Millis until the next damage over time tick
+    #[serde(skip, default = "dot_interval")]
+    pub dot_time: f32,
+}
+
+fn one() -> u32 { 1 }
+fn dot_interval() -> f32 { DOT_INTERVAL }
+
+impl StatusEffect {
+    pub fn new(ty: StatusEffectType, duration: f32, strength: u32) -> StatusEffect {
+        StatusEffect {
+            ty: ty,
+            duration: duration,
+            strength: strength,
+            stacks: 1,
+            dot_time: DOT_INTERVAL,
+        }
+    }
+
+    /// Move this effect on by dt millis. Returns any damage it does this tick.
+    pub fn tick(&mut self, dt: f32) -> Option<(u32, DamageType)> {
+        self.duration -= dt;
+        let damage_type = match self.ty {
+            StatusEffectType::Burning => DamageType::Fire,
+            StatusEffectType::Poisoned => DamageType::Poison,
+            _ => return None,
+        };
+        self.dot_time -= dt;
+        if self.dot_time > 0.0 { return None }
+        self.dot_time += DOT_INTERVAL;
+        Some((self.strength.saturating_mul(self.stacks), damage_type))
+    }
+}
+
+/// The status effects an entity is under. StatusEffectSys ticks these and
+/// removes them once they run out.
+#[derive(Component, Clone, Default, Debug)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    /// Apply an effect, following its stacking rule if it's already active
+    pub fn apply(&mut self, effect: StatusEffect) {
+        let existing = match self.effects.iter_mut().find(|e| e.ty == effect.ty) {
+            Some(existing) => existing,
+            None => return self.effects.push(effect),
+        };
+        existing.duration = existing.duration.max(effect.duration);
+        match effect.ty.stacking() {
+            Stacking::Refresh => existing.strength = existing.strength.max(effect.strength),
+            Stacking::Intensify { max_stacks } =>
+                existing.stacks = (existing.stacks + 1).min(max_stacks),
+        }
+    }
+
+    /// How much to multiply move speed by
+    pub fn move_speed_multiplier(&self) -> f32 {
+        self.effects.iter().filter(|e| e.ty == StatusEffectType::Slowed)
+            .map(|e| 1.0 - e.strength.min(100) as f32 / 100.0)
+            .fold(1.0, |total, m| total * m)
+    }
+
+    /// Are the controller / AI of this entity locked out?
+    pub fn locked_out(&self) -> bool {
+        self.effects.iter().any(|e| e.ty == StatusEffectType::Stunned)
+    }
+}
+
+/// If a Hurt hits something with Health, it also applies these status effects
+#[derive(Component, Clone)]
+pub struct HurtStatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacking_rules() {
+        let mut effects = StatusEffects::default();
+        effects.apply(StatusEffect::new(StatusEffectType::Slowed, 1000.0, 30));
+        effects.apply(StatusEffect::new(StatusEffectType::Slowed, 500.0, 50));
+        assert_eq!(effects.effects.len(), 1);
+        assert_eq!((effects.effects[0].duration, effects.effects[0].strength), (1000.0, 50));
+        assert_eq!(effects.move_speed_multiplier(), 0.5);
+
+        for _ in 0..10 {
+            effects.apply(StatusEffect::new(StatusEffectType::Poisoned, 2000.0, 1));
+        }
+        assert_eq!(effects.effects.len(), 2);
+        assert_eq!(effects.effects[1].stacks, 5);
+        assert!(!effects.locked_out());
+        effects.apply(StatusEffect::new(StatusEffectType::Stunned, 200.0, 0));
+        assert!(effects.locked_out());
+    }
+
+    #[test]
+    fn damage_over_time() {
+        let mut burning = StatusEffect::new(StatusEffectType::Burning, 1200.0, 3);
+        let mut total = 0;
+        while burning.duration > 0.0 {
+            if let Some((damage, ty)) = burning.tick(100.0) {
+                assert_eq!(ty, DamageType::Fire);
+                total += damage;
+            }
+        }
+        // Ticks at 500 and 1000 millis
+        assert_eq!(total, 6);
+        let mut slowed = StatusEffect::new(StatusEffectType::Slowed, 1000.0, 50);
+        assert_eq!(slowed.tick(1000.0), None);
+    }
+}
@@ -1,4 +1,5 @@
 use specs::*;
+use comp::{Pos, VelZ, Lifetime, DamageType, StatusEffectType};
 
 /// Apply a tint to this entity when drawing. This will apply to StaticSprite
 /// and AnimSprite components.
@@ -14,3 +15,94 @@ pub struct Rot {
     /// Rotation in radians, anti-clockwise
     pub angle: f32,
 }
+
+/// Text drawn upright in the world at this entity's Pos, e.g. damage numbers.
+/// Fades out over the entity's Lifetime, if it has one.
+#[derive(Component)]
+#[storage(HashMapStorage)]
+pub struct FloatingText {
+    pub text: String,
+    pub col: [f32; 4],
+    /// The starting lifetime of the entity in millis, which the text fades out
+    /// over
+    pub duration: f32,
+}
+
+/// How long combat text floats for, in millis
+const COMBAT_TEXT_DURATION : f32 = 800.0;
+/// How fast combat text rises, in units per second
+const COMBAT_TEXT_RISE_SPEED : f32 = 40.0;
+/// How high above the entity's feet combat text starts
+const COMBAT_TEXT_HEIGHT : f32 = 28.0;
+
+/// Floating text that shows what happened to an entity in combat
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[allow(dead_code)]
+pub enum CombatText {
+    /// Damage taken (after resistances)
+    Damage(u32, DamageType),
+    /// Health healed
+    Heal(u32),
+    /// A hit that was fully resisted
+    Immune,
+    /// A hit that did no damage at all
+    Miss,
+}
+
+impl CombatText {
+    pub fn text(self) -> String {
+        match self {
+            CombatText::Damage(amount, _) => amount.to_string(),
+            CombatText::Heal(amount) => format!("+{}", amount),
+            CombatText::Immune => "IMMUNE".to_string(),
+            CombatText::Miss => "MISS".to_string(),
+        }
+    }
+
+    /// Damage is coloured by its type, to match the tint of the status effect
+    /// of the same element. Heals are pink, so they can't be mistaken for
+    /// poison.
+    pub fn col(self) -> [f32; 4] {
+        match self {
+            CombatText::Damage(_, DamageType::Physical) => [1.0, 1.0, 1.0, 1.0],
+            CombatText::Damage(_, DamageType::Fire) => StatusEffectType::Burning.tint(),
+            CombatText::Damage(_, DamageType::Cold) => StatusEffectType::Slowed.tint(),
+            CombatText::Damage(_, DamageType::Poison) => StatusEffectType::Poisoned.tint(),
+            CombatText::Heal(_) => [1.0, 0.5, 0.75, 1.0],
+            CombatText::Immune | CombatText::Miss => [0.7, 0.7, 0.7, 1.0],
+        }
+    }
+
+    /// Build the text as an entity, rising from above something at pos
+    pub fn build<B: Builder>(self, builder: B, pos: &Pos) -> B {
+        builder
+            .with(Pos { pos: pos.pos, z: pos.z + COMBAT_TEXT_HEIGHT })
+            .with(VelZ { vel: COMBAT_TEXT_RISE_SPEED, gravity: 0.0, bounce: 0.0 })
+            .with(Lifetime { lifetime: COMBAT_TEXT_DURATION })
+            .with(FloatingText {
+                text: self.text(),
+                col: self.col(),
+                duration: COMBAT_TEXT_DURATION,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combat_text() {
+        assert_eq!(CombatText::Damage(12, DamageType::Fire).text(), "12");
+        assert_eq!(CombatText::Heal(2).text(), "+2");
+        assert_eq!(CombatText::Immune.text(), "IMMUNE");
+        // Different damage types, and heals, are told apart by colour
+        let cols : Vec<_> = [DamageType::Physical, DamageType::Fire,
+                             DamageType::Cold, DamageType::Poison].iter()
+            .map(|ty| CombatText::Damage(1, *ty).col())
+            .chain(Some(CombatText::Heal(1).col())).collect();
+        for (ix, a) in cols.iter().enumerate() {
+            assert!(cols[ix+1..].iter().all(|b| a != b));
+        }
+    }
+}
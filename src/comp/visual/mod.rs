@@ -1,5 +1,6 @@
 use specs::{DenseVecStorage, HashMapStorage};
-use renderer::{TextureKey, self, get_asset_by_name};
+use renderer::TextureKey;
+use biome::BiomeType;
 
 mod fx;
 mod camera;
@@ -83,39 +84,24 @@ impl AnimSprite {
     }
 }
 
-/// An anum of tilesets. Named this way to avoid collisions with
-/// renderer::atlas::Tileset.
-#[derive(Clone, Copy, Debug)]
-pub enum TilesetEnum {
-    /// 0 - Dirt
-    /// 1 - Grass
-    /// 2 - Water
-    Grass,
-}
-
-impl TilesetEnum {
-    pub fn convert_to_tex_key(&self) -> renderer::TextureKey {
-        match *self {
-            TilesetEnum::Grass => get_asset_by_name("TilesetGrass"),
-        }
-    }
-}
-
 /// The width / height of tilemaps.
 pub const TILEMAP_SIZE : usize = 16;
 
+/// The width / height of a single tile in world units.
+pub const TILE_SIZE : f32 = 32.0;
+
 /// A tilemap component. Coupled with a Pos component (for tilemap-wise offset,
 /// see below), this will render a tilemap at a given position with the given
-/// tileset.
+/// biome's tileset.
 /// Width is defined by TILEMAP_SIZE.
 /// The pos is multiplied by the chunk size, so a tilemap with pos (1, 0) and
 /// one with (2, 0) will be adjacent to one another.
 #[derive(Component)]
 #[storage(HashMapStorage)]
 pub struct Tilemap {
-    pub tileset: TilesetEnum,
-    /// Each u8 will correspond to a tile. This is defined implicitly by the
-    /// TilesetEnum.
+    pub biome: BiomeType,
+    /// Each u8 will correspond to a tile. This is an index into the biome's
+    /// palette.
     pub data: [u8; TILEMAP_SIZE * TILEMAP_SIZE],
 }
 
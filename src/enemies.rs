@@ -0,0 +1,55 @@
+//! A module for spawning enemies by type
+
+use specs::Builder;
+use comp::*;
+use vec::*;
+use drop_tables;
+use renderer::get_asset_by_name;
+use coll_layers::get_coll_filter_with_name;
+
+/// A type of enemy that can be spawned. Referenced by name in the biome files.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum EnemyType {
+    Slime,
+}
+
+impl EnemyType {
+    /// Add all the components for this enemy to an entity builder (either a
+    /// world EntityBuilder or a LazyBuilder), positioned at the given pos.
+    pub fn build<B: Builder>(self, builder: B, pos: Vec32) -> B {
+        match self {
+            EnemyType::Slime => builder
+                .with(Pos { pos: pos, z: 0.0 })
+                .with(Vel { vel: Vec32::zero() })
+                .with(Mass { mass: 1.5, restitution: Some(0.5) })
+                .with(Damping { drag: 6.3, friction: 60.0 })
+                .with(VelZ { vel: 0.0, gravity: 1000.0, bounce: 0.0 })
+                .with(Health::new(4, Hitmask(HITMASK_ENEMY))
+                      .with_resistance(DamageType::Poison, 50)
+                      .with_resistance(DamageType::Fire, -50))
+                .with(Hurt { damage: 2,
+                             damage_type: DamageType::Physical,
+                             mask: Hitmask::default_enemy_attack(),
+                             flags: 0 })
+                // Slime goo slows down whatever it hits
+                .with(HurtStatusEffects { effects: vec![
+                    StatusEffect::new(StatusEffectType::Slowed, 1000.0, 30)] })
+                .with(Alliance::evil())
+                .with(OnDeathDrop {
+                    drop_table: drop_tables::DropTableKey::Slime,
+                    min_drops: 1,
+                    max_drops: 3,
+                })
+                .with(AISlime { move_target: pos,
+                                attack_target: None,
+                                charge_time: 0.0,
+                                state: SlimeState::Idle })
+                .with(CollCircle { r: 8.0, off: Vec32::zero(), flags: COLL_SOLID | COLL_CCD,
+                                   filter: get_coll_filter_with_name("Character") })
+                .with(CollHeight { h: 16.0 })
+                .with(AnimSprite::new(32.0, 32.0, 100000.0,
+                                      1, get_asset_by_name("SlimeAnim"))
+                      .with_flags(ANIM_SPRITE_UPRIGHT)),
+        }
+    }
+}
@@ -20,7 +20,11 @@ pub enum Command {
     MoveUp,
     /// Used to attack, but also to navigate through dialogues
     Primary,
+    /// Used to use items in the inventory
+    Secondary,
     ToggleInventory,
+    /// Turns floating combat text on / off
+    ToggleCombatText,
 }
 
 /// A mapping of inputs to commands
@@ -37,7 +41,9 @@ impl InputMap {
         map.insert(Input::Key(glutin::VirtualKeyCode::S), Command::MoveDown);
         map.insert(Input::Key(glutin::VirtualKeyCode::D), Command::MoveRight);
         map.insert(Input::Key(glutin::VirtualKeyCode::Escape), Command::ToggleInventory);
+        map.insert(Input::Key(glutin::VirtualKeyCode::F1), Command::ToggleCombatText);
         map.insert(Input::Mouse(glutin::MouseButton::Left), Command::Primary);
+        map.insert(Input::Mouse(glutin::MouseButton::Right), Command::Secondary);
         InputMap {
             map: map,
         }
@@ -84,14 +90,18 @@ impl Default for InputState {
         down.insert(Command::MoveDown, false);
         down.insert(Command::MoveUp, false);
         down.insert(Command::Primary, false);
+        down.insert(Command::Secondary, false);
         down.insert(Command::ToggleInventory, false);
+        down.insert(Command::ToggleCombatText, false);
         let mut pressed = HashMap::new();
         pressed.insert(Command::MoveLeft, false);
         pressed.insert(Command::MoveRight, false);
         pressed.insert(Command::MoveDown, false);
         pressed.insert(Command::MoveUp, false);
         pressed.insert(Command::Primary, false);
+        pressed.insert(Command::Secondary, false);
         pressed.insert(Command::ToggleInventory, false);
+        pressed.insert(Command::ToggleCombatText, false);
         InputState {
             down: down,
             pressed: pressed,
@@ -110,10 +120,16 @@ impl InputState {
     }
 
     fn reset_state(&mut self) {
+        self.window_dimensions_need_update = false;
+    }
+
+    /// Forget which commands have just been pressed. This is done after each
+    /// simulation tick rather than when processing input, so every press is
+    /// seen by exactly one tick, however many ticks there are per frame.
+    pub fn clear_pressed(&mut self) {
         for (_, v) in self.pressed.iter_mut() {
             *v = false;
         }
-        self.window_dimensions_need_update = false;
     }
 
     /// Checks if the (screen) mouse is in the given XYWH rect.
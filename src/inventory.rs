@@ -93,6 +93,47 @@ impl Inventory {
 
     }
 
+    /// Removes one item from the stack in a slot, emptying the slot if that
+    /// was the last one. Returns the type of the item removed.
+    pub fn take_one(&mut self, slot: InventorySlotRef) -> Option<ItemType> {
+        let mut item = self.take_item(slot)?;
+        item.num -= 1;
+        if item.num > 0 {
+            let _ = self.set_item(slot, Some(item));
+        }
+        Some(item.item_type)
+    }
+
+    /// Count how many of an item type are in the inventory (not including
+    /// equipment slots)
+    pub fn count(&self, item_type: ItemType) -> u32 {
+        self.items.iter().filter_map(|i| *i)
+            .filter(|i| i.item_type == item_type)
+            .map(|i| i.num as u32).sum()
+    }
+
+    /// Remove up to num of an item type from the inventory (not including
+    /// equipment slots), emptying the slots they're taken from. Returns how
+    /// many were removed.
+    pub fn remove(&mut self, item_type: ItemType, num: u32) -> u32 {
+        let mut removed = 0;
+        for i_slot in self.items.iter_mut() {
+            if removed == num { break }
+            match i_slot {
+                Some(i) if i.item_type == item_type => {
+                    let n = (i.num as u32).min(num - removed);
+                    removed += n;
+                    i.num -= n as u8;
+                }
+                _ => continue,
+            }
+            if i_slot.map_or(false, |i| i.num == 0) {
+                *i_slot = None;
+            }
+        }
+        removed
+    }
+
     /// Checks if the given item type can go in the given inventory slot. This
     /// DOESN'T have any regard for the current stack size, it only 'type
     /// checks' (i.e. only helmets in the helmet slot)
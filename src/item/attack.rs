@@ -0,0 +1,128 @@
+//! Attacks made with weapons (or bare hands)
+
+use specs::Builder;
+use comp::*;
+use vec::*;
+use renderer::*;
+use coll_layers::get_coll_filter_with_name;
+
+/// Shape of an attack's hitbox
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum HitboxShape {
+    Circle { r: f32 },
+    Rect { w: f32, h: f32 },
+}
+
+/// The animation drawn for an attack's hitbox
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct AttackFx {
+    pub anim: TextureKey,
+    pub width: f32,
+    pub height: f32,
+    pub num_frames: usize,
+    /// Time per frame in millis
+    pub frame_time: f32,
+}
+
+/// Makes an attack's hitbox fly off as a Projectile
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ProjectileDetails {
+    pub speed: f32,
+    pub range: f32,
+    #[serde(default)]
+    pub pierce: u8,
+}
+
+/// What happens when a weapon is used to attack
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AttackDetails {
+    pub hitbox: HitboxShape,
+    /// How far from the attacker the hitbox is spawned
+    pub reach: f32,
+    pub damage: u32,
+    pub damage_type: DamageType,
+    /// Knockback impulse, in the direction of the attack
+    pub knockback: f32,
+    /// How long things are knocked back for, in millis
+    pub knockback_duration: f32,
+    /// How long the attacker is busy attacking for, in millis
+    pub cooldown: f32,
+    /// How long the hitbox lasts, in millis. Projectiles last until they run
+    /// out of range instead.
+    pub lifetime: f32,
+    pub fx: Option<AttackFx>,
+    pub projectile: Option<ProjectileDetails>,
+    /// Applied to whatever the attack hits
+    pub status_effects: Vec<StatusEffect>,
+}
+
+impl AttackDetails {
+    /// The attack used when no weapon is equipped
+    pub fn bare_hands() -> AttackDetails {
+        AttackDetails {
+            hitbox: HitboxShape::Circle { r: 24.0 },
+            reach: 12.0,
+            damage: 1,
+            damage_type: DamageType::Physical,
+            knockback: 300.0,
+            knockback_duration: 64.0,
+            cooldown: 125.0,
+            lifetime: 125.0,
+            fx: Some(AttackFx {
+                anim: get_asset_by_name("SliceAnim"),
+                width: 32.0,
+                height: 32.0,
+                num_frames: 5,
+                frame_time: 25.0,
+            }),
+            projectile: None,
+            status_effects: Vec::new(),
+        }
+    }
+
+    /// Add the components for this attack's hitbox to an entity builder. The
+    /// attack is made from pos towards dir (which must not be zero), and hurts
+    /// things in the given hitmask.
+    pub fn build<B: Builder>(&self, builder: B, pos: Vec32, dir: Vec32,
+                             damage_bonus: u32, mask: Hitmask) -> B {
+        let dir = dir.nor();
+        let (layer, flags) = match self.projectile {
+            Some(_) => ("Projectile", HURT_DIES),
+            None => ("Hitbox", 0),
+        };
+        let filter = get_coll_filter_with_name(layer);
+        let builder = builder
+            .with(Pos { pos: pos + dir * self.reach, z: 24.0 })
+            .with(Hurt { damage: self.damage.saturating_add(damage_bonus),
+                         damage_type: self.damage_type,
+                         mask: mask,
+                         flags: flags })
+            .with(HurtKnockbackDir { knockback: dir * self.knockback,
+                                     duration: self.knockback_duration })
+            .with(Rot { angle: dir.angle() });
+        let builder = match self.hitbox {
+            HitboxShape::Circle { r } => builder.with(CollCircle {
+                r: r, off: Vec32::zero(), flags: 0, filter: filter }),
+            HitboxShape::Rect { w, h } => builder.with(CollRect {
+                w: w, h: h, off: Vec32::zero(), flags: 0, filter: filter }),
+        };
+        let builder = if self.status_effects.is_empty() { builder } else {
+            builder.with(HurtStatusEffects { effects: self.status_effects.clone() })
+        };
+        let builder = match self.projectile {
+            Some(p) => builder
+                .with(Vel { vel: dir * p.speed })
+                .with(Projectile::new(p.speed, p.range, p.pierce)),
+            None => builder.with(Lifetime { lifetime: self.lifetime }),
+        };
+        match self.fx {
+            Some(fx) => {
+                // Projectiles keep animating while they fly
+                let flags = if self.projectile.is_some() { 0 } else { ANIM_SPRITE_NO_LOOP };
+                builder.with(AnimSprite::new(fx.width, fx.height, fx.frame_time,
+                                             fx.num_frames, fx.anim).with_flags(flags))
+            }
+            None => builder,
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Effects of using consumable items
+
+use comp::*;
+use enemies::EnemyType;
+
+/// Something that happens to the player when they use a consumable item. A
+/// consumable can have any number of these, which happen in order.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[allow(dead_code)]
+pub enum ConsumableEffect {
+    /// Heal this much health (up to the max health)
+    Heal(u32),
+    /// Apply a status effect to the player
+    StatusEffect(StatusEffect),
+    /// Teleport the player back to their spawn point
+    TeleportHome,
+    /// Spawn an enemy next to the player
+    Spawn(EnemyType),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_yaml;
+
+    #[test]
+    fn effects_parse() {
+        let effects : Vec<ConsumableEffect> = serde_yaml::from_str(
+            "[{ Heal: 2 }, TeleportHome, { Spawn: Slime }]").unwrap();
+        assert_eq!(effects, vec![ConsumableEffect::Heal(2),
+                                 ConsumableEffect::TeleportHome,
+                                 ConsumableEffect::Spawn(EnemyType::Slime)]);
+    }
+}
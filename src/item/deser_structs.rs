@@ -54,10 +54,75 @@ impl InWorldGfxDef {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AttackFxDef {
+    anim: String,
+    width: f32,
+    height: f32,
+    num_frames: usize,
+    frame_time: f32,
+}
+
+impl AttackFxDef {
+    fn link_assets(&self) -> AttackFx {
+        AttackFx {
+            anim: get_asset_by_name(&self.anim),
+            width: self.width,
+            height: self.height,
+            num_frames: self.num_frames,
+            frame_time: self.frame_time,
+        }
+    }
+}
+
+fn default_damage_type() -> DamageType { DamageType::Physical }
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AttackDef {
+    hitbox: HitboxShape,
+    #[serde(default)]
+    reach: f32,
+    damage: u32,
+    #[serde(default = "default_damage_type")]
+    damage_type: DamageType,
+    #[serde(default)]
+    knockback: f32,
+    #[serde(default)]
+    knockback_duration: f32,
+    cooldown: f32,
+    #[serde(default)]
+    lifetime: f32,
+    fx: Option<AttackFxDef>,
+    projectile: Option<ProjectileDetails>,
+    #[serde(default)]
+    status_effects: Vec<StatusEffect>,
+}
+
+impl AttackDef {
+    fn link_assets(&self) -> AttackDetails {
+        AttackDetails {
+            hitbox: self.hitbox,
+            reach: self.reach,
+            damage: self.damage,
+            damage_type: self.damage_type,
+            knockback: self.knockback,
+            knockback_duration: self.knockback_duration,
+            cooldown: self.cooldown,
+            lifetime: self.lifetime,
+            fx: self.fx.as_ref().map(|fx| fx.link_assets()),
+            projectile: self.projectile,
+            status_effects: self.status_effects.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EquipmentDataDef {
     equipment_type: EquipmentType,
     anim_key: Option<String>,
+    #[serde(default)]
+    stats: StatBlock,
+    attack: Option<AttackDef>,
 }
 
 impl EquipmentDataDef {
@@ -66,6 +131,8 @@ impl EquipmentDataDef {
         EquipmentData {
             equipment_type: self.equipment_type,
             anim_key: self.anim_key.as_ref().map(|s| get_asset_by_name(&s)),
+            stats: self.stats,
+            attack: self.attack.as_ref().map(|a| a.link_assets()),
         }
     }
 }
@@ -75,6 +142,7 @@ pub struct ItemDetailsDef {
     in_world_gfx: InWorldGfxDef,
     icon: String,
     equipment_data: Option<EquipmentDataDef>,
+    consumable: Option<Vec<ConsumableEffect>>,
     stacks: bool,
 }
 
@@ -86,6 +154,7 @@ impl ItemDetailsDef {
             in_world_gfx: self.in_world_gfx.link_assets(),
             icon: get_asset_by_name(&self.icon),
             equipment_data: self.equipment_data.as_ref().map(|edd| edd.link_assets()),
+            consumable: self.consumable.clone(),
             stacks: self.stacks,
             name: name,
         }
@@ -115,3 +184,30 @@ pub fn load_defs() -> BTreeMap<String, ItemDetailsDef> {
 
     items
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn item_defs_parse() {
+        let defs = load_defs();
+        let sword = defs["Bronze Sword"].equipment_data.as_ref().unwrap();
+        assert_eq!(sword.equipment_type, EquipmentType::Weapon);
+        let attack = sword.attack.as_ref().unwrap();
+        assert!(attack.projectile.is_none());
+        assert_eq!(attack.damage_type, DamageType::Physical);
+        let sling = defs["Sling"].equipment_data.as_ref().unwrap();
+        assert!(sling.attack.as_ref().unwrap().projectile.is_some());
+        assert_eq!(sling.attack.as_ref().unwrap().status_effects,
+                   vec![StatusEffect::new(StatusEffectType::Stunned, 300.0, 0)]);
+        let helmet = defs["Bronze Helmet"].equipment_data.as_ref().unwrap();
+        assert!(helmet.attack.is_none());
+        assert_eq!(helmet.stats.armour, 15);
+        assert!(defs["Bronze Helmet"].consumable.is_none());
+        assert_eq!(defs["Slime Jelly"].consumable,
+                   Some(vec![ConsumableEffect::Heal(2),
+                             ConsumableEffect::StatusEffect(
+                                 StatusEffect::new(StatusEffectType::Slowed, 1500.0, 30))]));
+    }
+}
@@ -2,6 +2,11 @@
 #![allow(dead_code)]
 
 mod deser_structs;
+mod attack;
+mod consumable;
+
+pub use self::attack::*;
+pub use self::consumable::*;
 
 use serde_yaml;
 use comp::*;
@@ -69,7 +74,7 @@ impl InWorldGfx {
     }
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct EquipmentData {
     equipment_type: EquipmentType,
     /// The key of the animation to use to render this equipment on a creature.
@@ -78,6 +83,10 @@ struct EquipmentData {
     /// 'wearing' the equipment).
     /// This is None if this is just a ring or weapon.
     anim_key: Option<TextureKey>,
+    /// Added to the wearer's stats
+    stats: StatBlock,
+    /// The attack made with this, if it's a weapon
+    attack: Option<AttackDetails>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -88,6 +97,9 @@ pub struct ItemDetails {
     icon: TextureKey,
     /// If this is equipment, this is Some.
     equipment_data: Option<EquipmentData>,
+    /// If this can be used up from the inventory, this is Some, and holds what
+    /// using it does.
+    consumable: Option<Vec<ConsumableEffect>>,
     /// If true, this item can stack (up to 99) in the inventory. Otherwise,
     /// separate stacks will be maintained.
     stacks: bool,
@@ -143,16 +155,34 @@ impl ItemType {
 
     pub fn equipment_type(self) -> Option<EquipmentType> {
         ITEM_REGISTER.read().unwrap().get_details(self)
-            .equipment_data.map(|d| d.equipment_type)
+            .equipment_data.as_ref().map(|d| d.equipment_type)
     }
 
     /// Panics if this item is not equipment, or if this is some equipment that doesn't have an animation (i.e. rings)
     pub fn get_equipment_anim(self) -> TextureKey {
         ITEM_REGISTER.read().unwrap().get_details(self)
-            .equipment_data.expect("Item is not equipment")
+            .equipment_data.as_ref().expect("Item is not equipment")
             .anim_key.expect("This equipment doesn't have an animation (is it a ring or weapon?)")
     }
 
+    /// Get the stat modifiers of this item when it's equipped. These are all 0
+    /// if this isn't equipment.
+    pub fn stat_modifiers(self) -> StatBlock {
+        ITEM_REGISTER.read().unwrap().get_details(self)
+            .equipment_data.as_ref().map_or(StatBlock::default(), |d| d.stats)
+    }
+
+    /// Get the attack made with this item, if it's a weapon
+    pub fn attack(self) -> Option<AttackDetails> {
+        ITEM_REGISTER.read().unwrap().get_details(self)
+            .equipment_data.as_ref().and_then(|d| d.attack.clone())
+    }
+
+    /// Get the effects of using this item, if it's a consumable
+    pub fn consumable(self) -> Option<Vec<ConsumableEffect>> {
+        ITEM_REGISTER.read().unwrap().get_details(self).consumable.clone()
+    }
+
     pub fn stacks(self) -> bool {
         ITEM_REGISTER.read().unwrap().get_details(self).stacks
     }
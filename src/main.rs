@@ -28,15 +28,26 @@ mod input;
 mod sys_control;
 mod sys_health;
 mod sys_phys;
+mod sys_tile_coll;
+mod sys_trigger;
 mod sys_anim;
 mod sys_lifetime;
 mod sys_on_hit;
 mod sys_pickup;
 mod sys_death_drop;
+mod sys_projectile;
 mod sys_track_pos;
+mod sys_prev_pos;
 mod sys_match_anim;
 mod sys_set_equipment;
+mod sys_stats;
+mod sys_status;
+mod sys_use_item;
+mod sys_respawn;
+mod sys_chunk_manager;
 mod vec;
+mod spatial_hash;
+mod phys_query;
 mod ui;
 mod camera;
 mod math_util;
@@ -44,6 +55,12 @@ mod item;
 mod inventory;
 mod drop_tables;
 mod asset_loader;
+mod worldgen;
+mod timestep;
+mod biome;
+mod coll_layers;
+mod enemies;
+mod settings;
 
 use comp::*;
 use vec::*;
@@ -54,9 +71,19 @@ use glutin::{GlRequest, GlContext};
 use glutin::Api::OpenGl;
 use std::time;
 use std::thread;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use renderer::get_asset_by_name;
 
+/// Simulation ticks per second
+const TICK_RATE : f32 = 60.0;
+/// The most simulation ticks to run per frame. If frames take longer than
+/// this, the game slows down.
+const MAX_TICKS_PER_FRAME : u32 = 5;
+/// Tilemap chunks within this many chunks of the camera are loaded
+const CHUNK_LOAD_RADIUS : i32 = 3;
+/// Tilemap chunks further than this many chunks from the camera are unloaded
+const CHUNK_UNLOAD_RADIUS : i32 = 5;
+
 pub struct CollisionMeta {
     /// This normal points outwards from entity B to entity A (and is also used
     /// to resolve circ - circ collisions)
@@ -68,8 +95,13 @@ pub struct CollisionMeta {
 /// Lists pairs of collisions.
 pub struct Collisions(Vec<(Entity, Entity, CollisionMeta)>);
 
+/// Length of a simulation tick, in seconds
 pub struct DeltaTime(pub f32);
 
+/// How far (0 to 1) rendering is between the last tick and the next one.
+/// Painters draw entities this far between their PrevPos and Pos.
+pub struct Interpolation(pub f32);
+
 /// Vertex buffer for game objects
 pub struct GameVertexBuffer(renderer::VertexBuffer);
 /// Vertex buffer for terrain (tilesets). This is so we don't have to re-buffer
@@ -78,14 +110,22 @@ pub struct GameVertexBuffer(renderer::VertexBuffer);
 pub struct TerrainVertexBuffer(renderer::VertexBuffer);
 /// If true, we should update the terrain vertex buffer.
 pub struct TerrainVertexBufferNeedsUpdate(bool);
+/// True if ChunkManagerSys loaded or unloaded any chunks this tick. Unlike
+/// TerrainVertexBufferNeedsUpdate, this isn't reset by the renderer.
+pub struct ChunksChanged(pub bool);
 /// Vertex buffer for UI objects (camera transform isn't applied)
 pub struct UIVertexBuffer(renderer::VertexBuffer);
 
 /// Entities that have been 'killed' and need to produce on-death effects. This
 /// doesn't mean all deleted entities - it means alive characters have been
-/// killed by combat or other effects.
+/// killed by combat or other effects, and projectiles / HURT_DIES hurts have
+/// been used up. Killed entities with Respawn aren't deleted at all -
+/// RespawnSys makes them Dead instead.
 pub struct KilledEntities(Vec<Entity>);
 
+/// Where the player starts, and goes back to when they teleport home
+pub struct SpawnPoint(pub Vec32);
+
 /// Empty specs::System to use in the dispatcher as a combiner for system
 /// dependencies.
 pub struct MarkerSys;
@@ -98,12 +138,19 @@ impl<'a> System<'a> for MarkerSys {
 fn create_world() -> specs::World {
     let mut world = specs::World::new();
     world.register::<Pos>();
+    world.register::<PrevPos>();
     world.register::<Vel>();
+    world.register::<Mass>();
+    world.register::<Damping>();
+    world.register::<VelZ>();
     world.register::<PlayerControlled>();
     world.register::<Tilemap>();
     world.register::<AnimSprite>();
     world.register::<StaticSprite>();
     world.register::<CollCircle>();
+    world.register::<CollRect>();
+    world.register::<CollHeight>();
+    world.register::<Trigger>();
     world.register::<AISlime>();
     world.register::<Hurt>();
     world.register::<Health>();
@@ -111,15 +158,24 @@ fn create_world() -> specs::World {
     world.register::<Knockback>();
     world.register::<HurtKnockbackDir>();
     world.register::<Tint>();
+    world.register::<FloatingText>();
     world.register::<Rot>();
     world.register::<Alliance>();
     world.register::<FollowCamera>();
     world.register::<Pickup>();
     world.register::<Collector>();
     world.register::<OnDeathDrop>();
+    world.register::<OnImpact>();
+    world.register::<Respawn>();
+    world.register::<Dead>();
+    world.register::<Checkpoint>();
+    world.register::<Projectile>();
     world.register::<TrackPos>();
     world.register::<MatchAnim>();
     world.register::<Equipment>();
+    world.register::<Stats>();
+    world.register::<StatusEffects>();
+    world.register::<HurtStatusEffects>();
     world
 }
 
@@ -140,76 +196,44 @@ fn main() {
     let (mut renderer, atlas) = renderer::Renderer::new(
         &mut factory, color_view, depth_view, Default::default());
 
-    // Load items
+    // Load items & biomes
     item::load_item_definitions();
+    biome::load_biome_definitions();
+    coll_layers::load_coll_layers();
 
     let camera = camera::Camera::new(w as f32, h as f32);
 
-    // Create the ECS world, and a test entity, plus trees
+    // Create the ECS world, and a test entity
     let mut world = create_world();
     use specs::Builder;
     // Player
-    let player = world.create_entity()
-        .with(Pos { pos: Vec32::new(32.0, 32.0), z: 0.0 })
+    let spawn_point = SpawnPoint(Vec32::new(32.0, 32.0));
+    world.create_entity()
+        .with(Pos { pos: spawn_point.0, z: 0.0 })
         .with(Vel { vel: Vec32::zero() })
+        .with(Damping { drag: 10.0, friction: 0.0 })
         .with(Alliance::good())
         .with(PlayerControlled::new())
         .with(FollowCamera)
         .with(Health::new(8, Hitmask(HITMASK_PLAYER)))
+        .with(Respawn { delay: 3000.0 })
         .with(Collector { magnet_radius: 64.0 })
         .with(Equipment {
             .. Default::default()
         })
+        .with(Stats::new(StatBlock { max_health: 8, armour: 0, damage_bonus: 0,
+                                     move_speed: 100.0 }))
         .with(CollCircle { r: 8.0, off: Vec32::zero(),
-                           flags: COLL_SOLID})
+                           flags: COLL_SOLID | COLL_CCD,
+                           filter: coll_layers::get_coll_filter_with_name("Character") })
         .with(AnimSprite::new(32.0, 32.0, 100.0,
                               4, get_asset_by_name("Human00Anim"))
               .with_flags(ANIM_SPRITE_UPRIGHT))
         .build();
-    // Tree
-    world.create_entity()
-        .with(Pos { pos: Vec32::new(100.0, 100.0), z: 0.0 })
-        .with(CollCircle { r: 12.0, off: Vec32::zero(),
-                           flags: COLL_SOLID | COLL_STATIC})
-        .with(StaticSprite { w: 64.0, h: 128.0,
-                             sprite: get_asset_by_name("GreenTree00"),
-                             flags: STATIC_SPRITE_UPRIGHT})
-        .build();
     // Slime
-    world.create_entity()
-        .with(Pos { pos: Vec32::new(200.0, 200.0), z: 0.0 })
-        .with(Vel { vel: Vec32::zero() })
-        .with(Health::new(4, Hitmask(HITMASK_ENEMY)))
-        .with(Hurt { damage: 2,
-                     mask: Hitmask::default_enemy_attack(),
-                     flags: 0 })
-        .with(Alliance::evil())
-        .with(OnDeathDrop {
-            drop_table: drop_tables::DropTableKey::Slime,
-            min_drops: 1,
-            max_drops: 3,
-        })
-        .with(AISlime { move_target: Vec32::new(200.0, 200.0),
-                        attack_target: None,
-                        charge_time: 0.0,
-                        state: SlimeState::Idle })
-        .with(CollCircle { r: 8.0, off: Vec32::zero(), flags: COLL_SOLID})
-        .with(AnimSprite::new(32.0, 32.0, 100000.0,
-                              1, get_asset_by_name("SlimeAnim"))
-              .with_flags(ANIM_SPRITE_UPRIGHT))
+    enemies::EnemyType::Slime.build(world.create_entity(), Vec32::new(200.0, 200.0))
         .build();
 
-    // Create tilemaps
-    for x in 0..10 {
-        for y in 0..10 {
-            world.create_entity()
-                .with(Pos { pos: Vec32::new(x as f32, y as f32), z: 0.0 })
-                .with(Tilemap { tileset: TilesetEnum::Grass,
-                                data: [1u8; TILEMAP_SIZE * TILEMAP_SIZE] })
-                .build();
-        }
-    }
-
     let mut inventory = inventory::Inventory::new();
     inventory.add_item(inventory::InventoryItem {
         item_type: item::get_item_type_with_name("Money").unwrap(),
@@ -219,6 +243,18 @@ fn main() {
         item_type: item::get_item_type_with_name("Bronze Helmet").unwrap(),
         num: 1,
     });
+    inventory.add_item(inventory::InventoryItem {
+        item_type: item::get_item_type_with_name("Bronze Sword").unwrap(),
+        num: 1,
+    });
+    inventory.add_item(inventory::InventoryItem {
+        item_type: item::get_item_type_with_name("Sling").unwrap(),
+        num: 1,
+    });
+    inventory.add_item(inventory::InventoryItem {
+        item_type: item::get_item_type_with_name("Slime Jelly").unwrap(),
+        num: 3,
+    });
 
     let input_map = input::InputMap::new();
     // Allocate cpu side v_buf
@@ -227,13 +263,22 @@ fn main() {
     // Add specs resources
     world.add_resource(atlas);
     world.add_resource(camera);
-    world.add_resource(DeltaTime(0.016));
+    world.add_resource(DeltaTime(1.0 / TICK_RATE));
+    world.add_resource(Interpolation(0.0));
     world.add_resource(Collisions(Vec::with_capacity(128)));
+    world.add_resource(sys_trigger::TriggerEvents(Vec::new()));
     world.add_resource::<ui::UIState>(Default::default());
+    world.add_resource(settings::Settings::default());
     world.add_resource(input::InputState::new());
     world.add_resource(drop_tables::DropTableMap::new_standard_map());
     world.add_resource(inventory);
     world.add_resource(KilledEntities(Vec::new()));
+    world.add_resource(spawn_point);
+    // Lose half your money when you die
+    world.add_resource(sys_respawn::DeathPenalty {
+        item: item::get_item_type_with_name("Money"),
+        percent: 50,
+    });
     world.add_resource(UIVertexBuffer(renderer::VertexBuffer {
         v_buf: v_buf.clone(), size: 0,
     }));
@@ -244,61 +289,105 @@ fn main() {
         v_buf: v_buf.clone(), size: 0,
     }));
     world.add_resource(TerrainVertexBufferNeedsUpdate(true));
+    world.add_resource(ChunksChanged(true));
+    world.add_resource(phys_query::PhysQuery::new(
+        coll_layers::get_coll_filter_with_name("Terrain").layer));
 
-    // Build dispatcher
+    // Build the simulation dispatcher, which is run once per tick
     let mut dispatcher = specs::DispatcherBuilder::new()
-        .with(sys_set_equipment::SetEquipmentSys, "set_equipment", &[])
-        .with(sys_lifetime::LifetimeSys, "lifetime", &[])
+        .with(sys_prev_pos::PrevPosSys, "prev_pos", &[])
+        .with(sys_set_equipment::SetEquipmentSys, "set_equipment", &["prev_pos"])
+        .with(sys_stats::StatsSys, "stats", &["set_equipment"])
+        .with(sys_lifetime::LifetimeSys, "lifetime", &["prev_pos"])
         // Control
-        .with(ui::UIInputSystem, "ui_input", &[])
-        .with(sys_control::PlayerControllerSys, "player_controller", &[])
-        .with(sys_control::SlimeAISys, "slime_ai", &[])
+        .with(ui::UIInputSystem, "ui_input", &["prev_pos"])
+        .with(sys_use_item::UseItemSys, "use_item", &["ui_input"])
+        .with(sys_control::PlayerControllerSys, "player_controller", &["stats", "use_item"])
+        .with(sys_control::SlimeAISys, "slime_ai", &["prev_pos"])
         .with(MarkerSys, "control", &["player_controller", "slime_ai", "ui_input"])
 
         // Animation
         .with(sys_anim::AnimSpriteSys, "anim_sprite", &["control"])
 
         // Physics
-        .with(sys_phys::PhysSys::<CollCircle, CollCircle>::new(), "phys_circ_circ", &["player_controller"])
-        .with(MarkerSys, "phys", &["phys_circ_circ"])
+        .with(sys_phys::MoveSys, "phys_move", &["player_controller"])
+        .with(sys_phys::PhysSys::<CollCircle, CollCircle>::new(), "phys_circ_circ", &["phys_move"])
+        .with(sys_phys::PhysSys::<CollCircle, CollRect>::new(), "phys_circ_rect", &["phys_move"])
+        .with(sys_phys::PhysSys::<CollRect, CollCircle>::new(), "phys_rect_circ", &["phys_move"])
+        .with(sys_phys::PhysSys::<CollRect, CollRect>::new(), "phys_rect_rect", &["phys_move"])
+        .with(sys_tile_coll::TileCollSys::<CollCircle>::new(), "phys_tile_circ",
+              &["phys_circ_circ", "phys_circ_rect"])
+        .with(sys_tile_coll::TileCollSys::<CollRect>::new(), "phys_tile_rect",
+              &["phys_rect_circ", "phys_rect_rect"])
+        .with(MarkerSys, "phys", &["phys_tile_circ", "phys_tile_rect"])
+
+        .with(sys_trigger::TriggerSys::new(), "triggers", &["phys"])
 
         .with(sys_track_pos::TrackPosSys, "track_pos", &["phys"])
         .with(sys_match_anim::MatchAnimSys, "match_anim", &["phys"])
 
-        // Camera control
-        .with(camera::FollowCameraSys, "follow_camera", &["phys"])
-
         // Pickups
         .with(sys_pickup::PickupSys, "pickup", &["phys"])
 
         // Combat
         .with(sys_health::HealthSys, "health",
-              &["phys", "set_equipment"])
+              &["phys", "stats"])
         .with(sys_on_hit::KnockbackSys, "oh_knockback",
               &["health", "set_equipment"])
+        .with(sys_projectile::ProjectileSys, "projectiles", &["health"])
+        .with(sys_status::StatusEffectSys, "status_effects", &["health"])
 
         .with(MarkerSys, "update",
-              &["phys", "anim_sprite", "health", "follow_camera",
-                "oh_knockback", "track_pos", "match_anim"])
+              &["phys", "anim_sprite", "health", "projectiles", "status_effects",
+                "oh_knockback", "track_pos", "match_anim", "triggers"])
 
         // After-death effects
         .with(sys_death_drop::OnDeathDropSys::new(
             rand::rngs::StdRng::from_rng(
                 rand::thread_rng()).unwrap()),
               "on_death_drop", &["update"])
+        .with(sys_projectile::OnImpactSys, "on_impact", &["update"])
+        .with(sys_respawn::RespawnSys::new(
+            rand::rngs::StdRng::from_rng(
+                rand::thread_rng()).unwrap()),
+              "respawn", &["update"])
+
+        // Stream terrain chunks in / out around the camera
+        .with(sys_chunk_manager::ChunkManagerSys::new(
+            worldgen::OverworldGen::new(rand::thread_rng().gen(),
+                                        biome::overworld_climates()),
+            CHUNK_LOAD_RADIUS, CHUNK_UNLOAD_RADIUS),
+              "chunk_manager", &["update"])
+
+        // Rebuild the collision world for next tick's queries
+        .with(phys_query::PhysQuerySys::new(), "phys_query", &["update", "chunk_manager"])
+        .build();
+
+    // Build the paint dispatcher, which is run once per frame
+    let mut paint_dispatcher = specs::DispatcherBuilder::new()
+        // Camera control
+        .with(camera::FollowCameraSys, "follow_camera", &[])
 
         // Paint
-        .with(renderer::TilemapPainter::new(), "tilemap_paint", &["update"])
-        .with(renderer::SpritePainter, "sprite_paint", &["update"])
-        .with(renderer::InventoryPainter, "ui_inventory_paint", &["update"])
+        .with(renderer::TilemapPainter, "tilemap_paint", &[])
+        .with(renderer::SpritePainter, "sprite_paint", &["follow_camera"])
+        .with(renderer::FloatingTextPainter, "floating_text_paint", &["sprite_paint"])
+        .with(renderer::InventoryPainter, "ui_inventory_paint", &[])
+        .with(renderer::GameOverPainter, "ui_game_over_paint", &["ui_inventory_paint"])
         .build();
 
     dispatcher.setup(&mut world.res);
+    paint_dispatcher.setup(&mut world.res);
+
+    let mut timestep = timestep::FixedTimestep::new(TICK_RATE, MAX_TICKS_PER_FRAME);
+    let mut last_frame = time::Instant::now();
 
     // Number of frames until we print another frame time
     let mut fps_count_timer = 60;
     loop {
         let start = time::Instant::now();
+        let frame_time = start - last_frame;
+        last_frame = start;
 
         // update input
         {
@@ -312,11 +401,35 @@ fn main() {
             }
         }
 
-        // Update & paint the world
-        {
+        // Update the world. The simulation is run in fixed ticks, so it
+        // behaves the same whatever the frame rate.
+        let ticks = timestep.advance(frame_time.as_secs() as f32
+                                     + frame_time.subsec_nanos() as f32 / 1e9);
+        for _ in 0..ticks {
             dispatcher.dispatch_seq(&mut world.res);
-            // Get the player position
-            let player_pos = world.read_storage::<Pos>().get(player).unwrap().clone();
+            // Clear per-tick state for the next tick
+            world.write_resource::<input::InputState>().clear_pressed();
+            world.write_resource::<Collisions>().0.clear();
+            world.write_resource::<KilledEntities>().0.clear();
+            // Actually delete all entities that need to be deleted
+            world.maintain();
+        }
+        world.write_resource::<Interpolation>().0 = timestep.alpha();
+
+        // Paint the world
+        {
+            paint_dispatcher.dispatch_seq(&mut world.res);
+            // Get the player position, or just look at the middle of the
+            // screen if there's no player
+            let player_pos = {
+                let pos_s = world.read_storage::<Pos>();
+                let pc_s = world.read_storage::<PlayerControlled>();
+                match (&*world.entities(), &pos_s, &pc_s).join().next() {
+                    Some((e, pos, _)) => pos.interpolate(
+                        world.read_storage::<PrevPos>().get(e), timestep.alpha()),
+                    None => Pos { pos: world.read_resource::<camera::Camera>().pos, z: 0.0 },
+                }
+            };
             let player_pos = [player_pos.pos.x, player_pos.z, player_pos.pos.y];
             let mut ui_v_buf = world.write_resource::<UIVertexBuffer>();
             let mut game_v_buf = world.write_resource::<GameVertexBuffer>();
@@ -348,16 +461,8 @@ fn main() {
             ui_v_buf.0.size = 0;
             game_v_buf.0.size = 0;
             terrain_v_buf.0.size = 0;
-            // Clear collision list for next frame
-            let mut collisions = world.write_resource::<Collisions>();
-            collisions.0.clear();
-            let mut killed = world.write_resource::<KilledEntities>();
-            killed.0.clear();
         }
 
-        // Actually delete all entities that need to be deleted
-        world.maintain();
-
         // Calculate frame time
         let elapsed = start.elapsed();
         if fps_count_timer <= 0 {
@@ -0,0 +1,422 @@
+//! Queries over the collision world - ray casts, segment casts and overlap
+//! tests against CollCircle colliders and solid tiles. The PhysQuery resource
+//! is rebuilt at the end of each frame by PhysQuerySys, so systems using it
+//! see where everything was at the end of the last frame.
+
+use std::collections::{BTreeMap, HashMap};
+use std::f32;
+use specs::*;
+use comp::*;
+use vec::*;
+use biome::BiomeType;
+use spatial_hash::SpatialHash;
+use ChunksChanged;
+
+/// Size of the cells in the spatial hash of circles
+const QUERY_CELL_SIZE : f32 = 64.0;
+
+/// Something hit by a query
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QueryHit {
+    /// The entity hit. For tiles, this is the Tilemap entity of the chunk.
+    pub entity: Entity,
+    /// The tile hit, if this was a tile
+    pub tile: Option<(i32, i32)>,
+    /// For casts, where the ray hit. For overlaps, the point on the collider
+    /// closest to the query's origin.
+    pub point: Vec32,
+    /// Normal of the collider's surface at the point. Will be normalised.
+    pub normal: Vec32,
+    /// Distance from the query's origin to the point
+    pub dis: f32,
+}
+
+/// A CollCircle in the query world
+struct QueryCircle {
+    entity: Entity,
+    centre: Vec32,
+    r: f32,
+    layer: u32,
+}
+
+/// The collision world, for answering queries. Queries take a mask of the
+/// collision layers they hit (i.e. CollFilter::layer bits). Colliders
+/// containing the origin of a cast are ignored, so a ray cast from an entity
+/// doesn't hit that entity.
+pub struct PhysQuery {
+    circles: Vec<QueryCircle>,
+    broad_phase: SpatialHash<usize>,
+    /// Positions of solid tiles, mapped to their chunk entity
+    solid_tiles: HashMap<(i32, i32), Entity>,
+    /// Collision layer bit of tiles
+    tile_layer: u32,
+}
+
+impl PhysQuery {
+    pub fn new(tile_layer: u32) -> PhysQuery {
+        PhysQuery {
+            circles: Vec::new(),
+            broad_phase: SpatialHash::new(QUERY_CELL_SIZE),
+            solid_tiles: HashMap::new(),
+            tile_layer: tile_layer,
+        }
+    }
+
+    pub fn clear_circles(&mut self) {
+        self.circles.clear();
+        self.broad_phase.clear();
+    }
+
+    pub fn add_circle(&mut self, entity: Entity, centre: Vec32, r: f32, layer: u32) {
+        let ix = self.circles.len();
+        self.circles.push(QueryCircle { entity, centre, r, layer });
+        self.broad_phase.insert(ix, [centre - Vec32::new(r, r), centre + Vec32::new(r, r)]);
+    }
+
+    pub fn clear_tiles(&mut self) {
+        self.solid_tiles.clear();
+    }
+
+    pub fn add_solid_tile(&mut self, chunk: Entity, x: i32, y: i32) {
+        self.solid_tiles.insert((x, y), chunk);
+    }
+
+    /// Get the indices of circles that might be in an AABB, on the given layers
+    fn circles_near(&self, min: Vec32, max: Vec32, mask: u32) -> Vec<&QueryCircle> {
+        let mut nearby = Vec::new();
+        self.broad_phase.query([min, max], &mut nearby);
+        nearby.into_iter().map(|ix| &self.circles[ix])
+            .filter(|c| c.layer & mask > 0).collect()
+    }
+
+    /// Cast a ray from the origin in the given direction, and return the first
+    /// thing hit within the max distance. Tiles are walked one at a time until
+    /// the max distance is reached, so nothing is hit if it's infinite, NaN or
+    /// negative.
+    pub fn raycast(&self, origin: Vec32, dir: Vec32, max_dis: f32, mask: u32) -> Option<QueryHit> {
+        if !(max_dis >= 0.0 && max_dis.is_finite()) || dir.len() == 0.0 { return None }
+        let dir = dir.nor();
+        let mut closest = if mask & self.tile_layer > 0 {
+            self.raycast_tiles(origin, dir, max_dis)
+        } else { None };
+
+        let end = origin + dir * max_dis;
+        let min = Vec32::new(origin.x.min(end.x), origin.y.min(end.y));
+        let max = Vec32::new(origin.x.max(end.x), origin.y.max(end.y));
+        for c in self.circles_near(min, max, mask) {
+            let m = origin - c.centre;
+            let b = m.dot(dir);
+            let cc = m.dot(m) - c.r * c.r;
+            // Starts inside the circle, or points away from it
+            if cc <= 0.0 || b > 0.0 { continue }
+            let disc = b * b - cc;
+            if disc < 0.0 { continue }
+            let t = -b - disc.sqrt();
+            if t > closest.map_or(max_dis, |h| h.dis) { continue }
+            let point = origin + dir * t;
+            closest = Some(QueryHit {
+                entity: c.entity,
+                tile: None,
+                point: point,
+                normal: (point - c.centre).nor(),
+                dis: t,
+            });
+        }
+        closest
+    }
+
+    /// Walk the tiles along a ray until a solid one is hit, or the max distance
+    /// (which must be finite) is reached
+    fn raycast_tiles(&self, origin: Vec32, dir: Vec32, max_dis: f32) -> Option<QueryHit> {
+        let mut tile = ((origin.x / TILE_SIZE).floor() as i32,
+                        (origin.y / TILE_SIZE).floor() as i32);
+        let step = (if dir.x > 0.0 { 1 } else { -1 }, if dir.y > 0.0 { 1 } else { -1 });
+        // Distance along the ray to the next tile boundary on each axis, and
+        // the distance between boundaries
+        let boundary = |t: i32, step: i32, o: f32, d: f32| -> (f32, f32) {
+            if d == 0.0 { return (f32::INFINITY, f32::INFINITY) }
+            let next = (t + if step > 0 { 1 } else { 0 }) as f32 * TILE_SIZE;
+            ((next - o) / d, TILE_SIZE / d.abs())
+        };
+        let (mut next_x, delta_x) = boundary(tile.0, step.0, origin.x, dir.x);
+        let (mut next_y, delta_y) = boundary(tile.1, step.1, origin.y, dir.y);
+        loop {
+            let (t, normal) = if next_x < next_y {
+                tile.0 += step.0;
+                next_x += delta_x;
+                (next_x - delta_x, Vec32::new(-step.0 as f32, 0.0))
+            } else {
+                tile.1 += step.1;
+                next_y += delta_y;
+                (next_y - delta_y, Vec32::new(0.0, -step.1 as f32))
+            };
+            if t > max_dis { return None }
+            if let Some(&e) = self.solid_tiles.get(&tile) {
+                return Some(QueryHit {
+                    entity: e,
+                    tile: Some(tile),
+                    point: origin + dir * t,
+                    normal: normal,
+                    dis: t,
+                });
+            }
+        }
+    }
+
+    /// Cast a ray from one point to another, and return the first thing hit
+    pub fn segment_cast(&self, from: Vec32, to: Vec32, mask: u32) -> Option<QueryHit> {
+        self.raycast(from, to - from, (to - from).len(), mask)
+    }
+
+    /// Is there nothing on the given layers between the 2 points?
+    pub fn line_of_sight(&self, from: Vec32, to: Vec32, mask: u32) -> bool {
+        self.segment_cast(from, to, mask).is_none()
+    }
+
+    /// Get everything overlapping a circle, closest first
+    #[allow(dead_code)]
+    pub fn overlap_circle(&self, centre: Vec32, r: f32, mask: u32) -> Vec<QueryHit> {
+        let mut hits = Vec::new();
+        let (min, max) = (centre - Vec32::new(r, r), centre + Vec32::new(r, r));
+        for c in self.circles_near(min, max, mask) {
+            let vec = centre - c.centre;
+            let dis = vec.len();
+            if dis >= r + c.r { continue }
+            let normal = if dis > 0.0 { vec / dis } else { Vec32::new(1.0, 0.0) };
+            hits.push(QueryHit {
+                entity: c.entity,
+                tile: None,
+                point: c.centre + normal * c.r.min(dis),
+                normal: normal,
+                dis: (dis - c.r).max(0.0),
+            });
+        }
+
+        if mask & self.tile_layer > 0 {
+            let (min_x, min_y) = ((min.x / TILE_SIZE).floor() as i32,
+                                  (min.y / TILE_SIZE).floor() as i32);
+            let (max_x, max_y) = ((max.x / TILE_SIZE).floor() as i32,
+                                  (max.y / TILE_SIZE).floor() as i32);
+            for y in min_y..max_y+1 {
+                for x in min_x..max_x+1 {
+                    let e = match self.solid_tiles.get(&(x, y)) {
+                        Some(&e) => e,
+                        None => continue,
+                    };
+                    let tile_min = Vec32::new(x as f32, y as f32) * TILE_SIZE;
+                    let closest = Vec32::new(
+                        centre.x.max(tile_min.x).min(tile_min.x + TILE_SIZE),
+                        centre.y.max(tile_min.y).min(tile_min.y + TILE_SIZE));
+                    let vec = centre - closest;
+                    let dis = vec.len();
+                    if dis >= r { continue }
+                    let tile_centre = tile_min + Vec32::new(TILE_SIZE, TILE_SIZE) / 2.0;
+                    let normal = if dis > 0.0 { vec / dis }
+                    else if centre != tile_centre { (centre - tile_centre).nor() }
+                    else { Vec32::new(1.0, 0.0) };
+                    hits.push(QueryHit {
+                        entity: e,
+                        tile: Some((x, y)),
+                        point: closest,
+                        normal: normal,
+                        dis: dis,
+                    });
+                }
+            }
+        }
+
+        hits.sort_by(|a, b| a.dis.partial_cmp(&b.dis).unwrap());
+        hits
+    }
+
+    /// Get everything overlapping a cone, closest first. The cone spreads
+    /// half_angle (in radians) either side of dir. Something is in the cone
+    /// if the point on it closest to the origin is.
+    #[allow(dead_code)]
+    pub fn overlap_cone(&self, origin: Vec32, dir: Vec32, half_angle: f32,
+                        range: f32, mask: u32) -> Vec<QueryHit> {
+        let dir = dir.nor();
+        let min_cos = half_angle.cos();
+        self.overlap_circle(origin, range, mask).into_iter().filter(|h| {
+            let vec = h.point - origin;
+            vec.len() == 0.0 || vec.nor().dot(dir) >= min_cos
+        }).collect()
+    }
+}
+
+/// Rebuilds the PhysQuery resource from the positions of colliders. Solid
+/// tiles are only rebuilt when chunks have changed.
+pub struct PhysQuerySys {
+    /// Which tile ids are solid, for every biome seen so far
+    solid_tiles: BTreeMap<BiomeType, Vec<bool>>,
+}
+
+impl PhysQuerySys {
+    pub fn new() -> PhysQuerySys {
+        PhysQuerySys {
+            solid_tiles: BTreeMap::new(),
+        }
+    }
+}
+
+impl<'a> System<'a> for PhysQuerySys {
+    type SystemData = (
+        WriteExpect<'a, PhysQuery>,
+        ReadExpect<'a, ChunksChanged>,
+        Entities<'a>,
+        ReadStorage<'a, Pos>,
+        ReadStorage<'a, CollCircle>,
+        ReadStorage<'a, Tilemap>);
+
+    fn run(&mut self, (mut query, chunks_changed, entities_s, pos_s,
+                       coll_s, tm_s): Self::SystemData) {
+        query.clear_circles();
+        for (e, pos, coll) in (&*entities_s, &pos_s, &coll_s).join() {
+            query.add_circle(e, pos.pos + coll.off, coll.r, coll.filter.layer);
+        }
+
+        if !chunks_changed.0 { return }
+        query.clear_tiles();
+        let size = TILEMAP_SIZE as i32;
+        for (e, pos, tm) in (&*entities_s, &pos_s, &tm_s).join() {
+            let solid = self.solid_tiles.entry(tm.biome)
+                .or_insert_with(|| tm.biome.solid_tiles());
+            let (cx, cy) = (pos.pos.x as i32, pos.pos.y as i32);
+            for (ix, &t) in tm.data.iter().enumerate() {
+                if *solid.get(t as usize).unwrap_or(&false) {
+                    query.add_solid_tile(e, cx * size + ix as i32 % size,
+                                         cy * size + ix as i32 / size);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TILE : u32 = 1;
+    const CHAR : u32 = 2;
+
+    fn assert_near(a: Vec32, b: Vec32) {
+        assert!((a - b).len() < 0.001, "{:?} != {:?}", a, b);
+    }
+
+    /// A query world with a wall of tiles at x = 4, and 2 circles
+    fn create_query() -> (World, PhysQuery, Entity, Entity, Entity) {
+        let mut world = World::new();
+        let chunk = world.create_entity().build();
+        let a = world.create_entity().build();
+        let b = world.create_entity().build();
+        let mut query = PhysQuery::new(TILE);
+        for y in -5..5 {
+            query.add_solid_tile(chunk, 4, y);
+        }
+        query.add_circle(a, Vec32::new(64.0, 16.0), 10.0, CHAR);
+        query.add_circle(b, Vec32::new(-64.0, 16.0), 10.0, CHAR);
+        (world, query, chunk, a, b)
+    }
+
+    #[test]
+    fn raycast_hits_closest_circle() {
+        let (_w, query, _chunk, a, _b) = create_query();
+        let hit = query.raycast(Vec32::new(0.0, 16.0), Vec32::new(1.0, 0.0),
+                                1000.0, TILE | CHAR).unwrap();
+        assert_eq!(hit.entity, a);
+        assert_eq!(hit.tile, None);
+        assert_near(hit.point, Vec32::new(54.0, 16.0));
+        assert_near(hit.normal, Vec32::new(-1.0, 0.0));
+        assert!((hit.dis - 54.0).abs() < 0.001);
+        // Out of range
+        assert!(query.raycast(Vec32::new(0.0, 16.0), Vec32::new(1.0, 0.0),
+                              50.0, TILE | CHAR).is_none());
+    }
+
+    #[test]
+    fn raycast_hits_tiles_and_respects_mask() {
+        let (_w, query, chunk, _a, _b) = create_query();
+        // Only hit tiles, so we go through circle a to the wall at x = 128
+        let hit = query.raycast(Vec32::new(0.0, 16.0), Vec32::new(1.0, 0.0),
+                                1000.0, TILE).unwrap();
+        assert_eq!(hit.entity, chunk);
+        assert_eq!(hit.tile, Some((4, 0)));
+        assert_near(hit.point, Vec32::new(128.0, 16.0));
+        assert_near(hit.normal, Vec32::new(-1.0, 0.0));
+        // Diagonal ray hits the wall
+        let hit = query.raycast(Vec32::new(100.0, 0.0), Vec32::new(1.0, 1.0),
+                                1000.0, TILE).unwrap();
+        assert_eq!(hit.tile, Some((4, 0)));
+        assert_near(hit.point, Vec32::new(128.0, 28.0));
+        // Nothing on the char layer to the left except b
+        let hit = query.raycast(Vec32::new(0.0, 16.0), Vec32::new(-1.0, 0.0),
+                                1000.0, TILE | CHAR).unwrap();
+        assert_eq!(hit.tile, None);
+        assert!(query.raycast(Vec32::new(0.0, 16.0), Vec32::new(-1.0, 0.0),
+                              1000.0, TILE).is_none());
+    }
+
+    #[test]
+    fn raycast_with_infinite_max_dis_returns() {
+        let (_w, query, _chunk, _a, _b) = create_query();
+        assert!(query.raycast(Vec32::new(0.0, 16.0), Vec32::new(0.0, 1.0),
+                              f32::INFINITY, TILE | CHAR).is_none());
+        assert!(query.raycast(Vec32::new(0.0, 16.0), Vec32::new(0.0, 1.0),
+                              f32::NAN, TILE | CHAR).is_none());
+        assert!(query.raycast(Vec32::new(0.0, 16.0), Vec32::new(1.0, 0.0),
+                              -1.0, TILE | CHAR).is_none());
+    }
+
+    #[test]
+    fn casts_ignore_colliders_containing_origin() {
+        let (_w, query, _chunk, _a, b) = create_query();
+        let hit = query.raycast(Vec32::new(64.0, 16.0), Vec32::new(-1.0, 0.0),
+                                1000.0, CHAR).unwrap();
+        assert_eq!(hit.entity, b);
+    }
+
+    #[test]
+    fn segment_cast_and_line_of_sight() {
+        let (_w, query, _chunk, _a, _b) = create_query();
+        // Wall between (100, 16) and (200, 16)
+        assert!(!query.line_of_sight(Vec32::new(100.0, 16.0), Vec32::new(200.0, 16.0), TILE));
+        // Nothing between (100, 16) and (120, 16)
+        assert!(query.line_of_sight(Vec32::new(100.0, 16.0), Vec32::new(120.0, 16.0), TILE));
+        let hit = query.segment_cast(Vec32::new(200.0, 16.0), Vec32::new(100.0, 16.0),
+                                     TILE).unwrap();
+        assert_eq!(hit.tile, Some((4, 0)));
+        assert_near(hit.point, Vec32::new(160.0, 16.0));
+        assert_near(hit.normal, Vec32::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn overlap_circle_finds_circles_and_tiles() {
+        let (_w, query, _chunk, a, _b) = create_query();
+        let hits = query.overlap_circle(Vec32::new(100.0, 16.0), 40.0, TILE | CHAR);
+        assert_eq!(hits.len(), 4);
+        // Closest is the circle, then the wall tile at (4, 0), then the tiles
+        // either side of it
+        assert_eq!(hits[0].entity, a);
+        assert_near(hits[0].point, Vec32::new(74.0, 16.0));
+        assert_near(hits[0].normal, Vec32::new(1.0, 0.0));
+        assert_eq!(hits[1].tile, Some((4, 0)));
+        assert_near(hits[1].point, Vec32::new(128.0, 16.0));
+        assert_near(hits[1].normal, Vec32::new(-1.0, 0.0));
+        let hits = query.overlap_circle(Vec32::new(100.0, 16.0), 40.0, CHAR);
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn overlap_cone_filters_by_angle() {
+        let (_w, query, _chunk, a, b) = create_query();
+        let right = query.overlap_cone(Vec32::new(0.0, 16.0), Vec32::new(1.0, 0.0),
+                                       0.5, 100.0, CHAR);
+        assert_eq!(right.iter().map(|h| h.entity).collect::<Vec<_>>(), vec![a]);
+        let left = query.overlap_cone(Vec32::new(0.0, 16.0), Vec32::new(-1.0, 0.0),
+                                      0.5, 100.0, CHAR);
+        assert_eq!(left.iter().map(|h| h.entity).collect::<Vec<_>>(), vec![b]);
+        let up = query.overlap_cone(Vec32::new(0.0, 16.0), Vec32::new(0.0, -1.0),
+                                    0.5, 100.0, CHAR);
+        assert!(up.is_empty());
+    }
+}
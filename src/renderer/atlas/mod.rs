@@ -73,6 +73,17 @@ impl UvRect {
     }
 }
 
+/// Bits for autotile neighbour masks. A bit is set if the neighbour in that
+/// direction is the same tile.
+pub const AUTOTILE_N : u8 = 1;
+pub const AUTOTILE_E : u8 = 2;
+pub const AUTOTILE_S : u8 = 4;
+pub const AUTOTILE_W : u8 = 8;
+
+/// The tile (x, y in the tileset) to draw for every combination of matching
+/// neighbours, indexed by a mask of the AUTOTILE_* bits.
+pub type AutotileTable = [[u32; 2]; 16];
+
 #[derive(Debug, Clone)]
 pub struct Tileset {
     /// The rect fot the whole tileset
@@ -81,6 +92,9 @@ pub struct Tileset {
     pub w: u32,
     /// Height in tiles
     pub h: u32,
+    /// Maps tiles to their transition tiles. Tiles that aren't in here are
+    /// always drawn as-is.
+    autotiles: BTreeMap<[u32; 2], AutotileTable>,
 }
 
 impl Tileset {
@@ -89,14 +103,31 @@ impl Tileset {
     /// Same as UvRect::from_pixel_rect, but with:
     /// * `tiles_x` - The amount of tiles in this tileset width-wise
     /// * `tiles_y` - The amount of tiles in this tileset width-wise
-    pub fn from_pixel_rect(rect: &[u16; 4], w: u16, h: u16, tiles_x: u32, tiles_y: u32) -> Self {
+    /// * `autotiles` - Transition tables for tiles which should be autotiled
+    pub fn from_pixel_rect(rect: &[u16; 4], w: u16, h: u16, tiles_x: u32, tiles_y: u32,
+                           autotiles: BTreeMap<[u32; 2], AutotileTable>) -> Self {
         Tileset {
             rect: UvRect::from_pixel_rect(rect, w, h),
             w: tiles_x,
             h: tiles_y,
+            autotiles: autotiles,
         }
     }
 
+    /// Get the tile to draw for a given tile, given a mask of which neighbours
+    /// are the same tile (see the AUTOTILE_* consts).
+    pub fn autotile(&self, x: u32, y: u32, mask: u8) -> [u32; 2] {
+        match self.autotiles.get(&[x, y]) {
+            Some(table) => table[mask as usize & 15],
+            None => [x, y],
+        }
+    }
+
+    /// Returns true if the given tile has transition tiles
+    pub fn is_autotiled(&self, x: u32, y: u32) -> bool {
+        self.autotiles.contains_key(&[x, y])
+    }
+
     /// Get the UvRect of a given tile in this set
     pub fn tile(&self, x: u32, y: u32) -> UvRect {
         let total_w = self.rect.right - self.rect.left;
@@ -213,8 +244,12 @@ impl<K : Ord> AtlasBuilder<K> {
     /// # Params
     /// * `tiles_x` - Amount of tiles width-wise
     /// * `tiles_y` - Amount of tiles height-wise
+    /// * `autotiles` - Transition tables for tiles which should be autotiled,
+    /// keyed by the tile.
     pub fn add_tileset<P: AsRef<Path>>(&mut self, key: K, img_path: P,
-                                   tiles_x: u32, tiles_y: u32) -> Result<(), AtlasPackErr> {
+                                       tiles_x: u32, tiles_y: u32,
+                                       autotiles: BTreeMap<[u32; 2], AutotileTable>)
+                                       -> Result<(), AtlasPackErr> {
         // Load the texture
         let img = image::open(img_path)?.to_rgba();
         let (w, h) = img.dimensions();
@@ -228,7 +263,7 @@ impl<K : Ord> AtlasBuilder<K> {
         self.atlas.tilesets.insert(
             key,
             Tileset::from_pixel_rect(&pixel_rect_unpadded, self.width,
-                                     self.height, tiles_x, tiles_y));
+                                     self.height, tiles_x, tiles_y, autotiles));
         self.blit(&img_buf[..], &pixel_rect_unpadded);
         Ok(())
     }
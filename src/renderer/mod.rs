@@ -260,6 +260,41 @@ impl Renderer {
         v_buf[5] = Vertex {pos: [p2[0], z, p2[1]], col: col, uv: [tex.right, tex.bottom]};
     }
 
+    /// Render a line of text with a bitmap font on the horizontal plane. (x, y)
+    /// is the top left of the text. Glyphs are spaced a third of their width
+    /// apart, which is 1 pixel for the tiny fonts. Chars the font doesn't have
+    /// are left as gaps.
+    /// Returns how many vertices were used.
+    /// # Params
+    /// * upright - Render the text 'upright' (like upright sprites), in which
+    /// case (x, z) is the bottom left of the text.
+    fn text(v_buf: &mut [Vertex], font: &BitmapFont, text: &str,
+            x: f32, y: f32, z: f32,
+            glyph_w: f32, glyph_h: f32,
+            col: [f32; 4], upright: bool) -> usize {
+        let mut ix = 0;
+        for (i, c) in text.chars().enumerate() {
+            if let Some(tex) = font.rect_for_char(c) {
+                let x = x + i as f32 * glyph_w * 4.0 / 3.0;
+                if upright {
+                    Renderer::rect_upright(&mut v_buf[ix .. ix+6], tex, x, y, z,
+                                           glyph_w, glyph_h, col);
+                } else {
+                    Renderer::rect(&mut v_buf[ix .. ix+6], tex, x, y, z,
+                                   glyph_w, glyph_h, col);
+                }
+                ix += 6;
+            }
+        }
+        ix
+    }
+
+    /// The width of some text drawn with Renderer::text
+    fn text_width(text: &str, glyph_w: f32) -> f32 {
+        let len = text.chars().count() as f32;
+        if len == 0.0 { 0.0 } else { len * glyph_w * 4.0 / 3.0 - glyph_w / 3.0 }
+    }
+
     pub fn update_window_size(&mut self, window: &GlWindow) {
         // Update the render target size
         gfx_window_glutin::update_views(window, &mut self.ui_pipe_data.out_col,
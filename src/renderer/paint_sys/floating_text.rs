@@ -0,0 +1,51 @@
+use specs::*;
+use super::*;
+use settings::Settings;
+use {GameVertexBuffer, Interpolation};
+
+/// Paints FloatingText upright in the world, centred on its Pos, fading it out
+/// over its Lifetime. Nothing is painted if combat text is turned off in the
+/// Settings.
+pub struct FloatingTextPainter;
+
+/// The size of a glyph of the tiny font in the world, i.e. 2 units per pixel
+const GLYPH_W : f32 = 6.0;
+const GLYPH_H : f32 = 10.0;
+
+impl<'a> System<'a> for FloatingTextPainter {
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, GameVertexBuffer>,
+        ReadExpect<'a, TextureAtlas<TextureKey>>,
+        ReadExpect<'a, Interpolation>,
+        ReadExpect<'a, Settings>,
+        ReadStorage<'a, Pos>,
+        ReadStorage<'a, PrevPos>,
+        ReadStorage<'a, Lifetime>,
+        ReadStorage<'a, FloatingText>);
+
+    fn run(&mut self, (entities_s, mut vertex_buffer, atlas, interpolation, settings,
+                       pos_s, prev_pos_s, lifetime_s, text_s): Self::SystemData) {
+        if !settings.combat_text { return }
+
+        let vertex_buffer = &mut vertex_buffer.0;
+        let font = atlas.bitmap_font(get_asset_by_name("FontTiny")).unwrap();
+
+        let mut ix = vertex_buffer.size as usize;
+        for (e, pos, text) in (&*entities_s, &pos_s, &text_s).join() {
+            let pos = pos.interpolate(prev_pos_s.get(e), interpolation.0);
+            let mut col = text.col;
+            if let Some(lifetime) = lifetime_s.get(e) {
+                col[3] *= (lifetime.lifetime / text.duration).max(0.0).min(1.0);
+            }
+            // Nudged towards the camera, so it's drawn in front of whatever
+            // it's floating over
+            ix += Renderer::text(&mut vertex_buffer.v_buf[ix..], font, &text.text,
+                                 pos.pos.x - Renderer::text_width(&text.text, GLYPH_W) / 2.0,
+                                 pos.pos.y + 1.0, -pos.z,
+                                 GLYPH_W, GLYPH_H, col, true);
+        }
+
+        vertex_buffer.size = ix as u32;
+    }
+}
@@ -2,13 +2,19 @@ use super::*;
 use specs::*;
 use comp::*;
 use comp;
-use {GameVertexBuffer, TerrainVertexBuffer, TerrainVertexBufferNeedsUpdate};
+use biome::BiomeType;
+use std::collections::{HashMap, BTreeMap};
+use {GameVertexBuffer, TerrainVertexBuffer, TerrainVertexBufferNeedsUpdate, Interpolation};
 mod ui_inventory;
+mod ui_game_over;
+mod floating_text;
 
 pub use self::ui_inventory::{
     InventoryPainter,
     NUM_COLUMNS as INVENTORY_NUM_COLUMNS,
     SLOT_SIZE as INVENTORY_SLOT_SIZE};
+pub use self::ui_game_over::GameOverPainter;
+pub use self::floating_text::FloatingTextPainter;
 
 #[derive(Clone, Debug)]
 pub struct VertexBuffer {
@@ -16,7 +22,8 @@ pub struct VertexBuffer {
     pub size: u32,
 }
 
-/// Paints components with AnimSprite or StaticSprite and Pos.
+/// Paints components with AnimSprite or StaticSprite and Pos. Positions are
+/// interpolated between the last 2 ticks.
 pub struct SpritePainter;
 
 impl SpritePainter {
@@ -73,15 +80,17 @@ impl<'a> System<'a> for SpritePainter {
         Entities<'a>,
         WriteExpect<'a, GameVertexBuffer>,
         ReadExpect<'a, TextureAtlas<TextureKey>>,
+        ReadExpect<'a, Interpolation>,
         ReadStorage<'a, Pos>,
+        ReadStorage<'a, PrevPos>,
         ReadStorage<'a, Equipment>,
         ReadStorage<'a, Tint>,
         ReadStorage<'a, Rot>,
         ReadStorage<'a, comp::AnimSprite>,
         ReadStorage<'a, StaticSprite>);
 
-    fn run(&mut self, (entities_s, mut vertex_buffer, atlas, pos_s,
-                       equipment_s, tint_s, rot_s, anim_s, static_s):
+    fn run(&mut self, (entities_s, mut vertex_buffer, atlas, interpolation, pos_s,
+                       prev_pos_s, equipment_s, tint_s, rot_s, anim_s, static_s):
            Self::SystemData) {
         use specs::Join;
 
@@ -90,6 +99,7 @@ impl<'a> System<'a> for SpritePainter {
         // Animated
         let mut ix = vertex_buffer.size as usize;
         for (e, pos, anim) in (&*entities_s, &pos_s, &anim_s).join() {
+            let pos = pos.interpolate(prev_pos_s.get(e), interpolation.0);
             let tex = atlas.rect_for_anim_sprite(anim.anim_key.clone()).unwrap()
                 .frame(anim.anim, anim.curr_frame, &atlas.frame_set_map);
             SpritePainter::draw_sprite(
@@ -101,6 +111,7 @@ impl<'a> System<'a> for SpritePainter {
 
         // Static
         for (e, pos, sprite) in (&*entities_s, &pos_s, &static_s).join() {
+            let pos = pos.interpolate(prev_pos_s.get(e), interpolation.0);
             let tex = atlas.rect_for_tex(sprite.sprite.clone()).unwrap();
             SpritePainter::draw_sprite(
                 vertex_buffer, &mut ix, &pos, e, sprite.w, sprite.h, &tint_s, &rot_s,
@@ -111,6 +122,7 @@ impl<'a> System<'a> for SpritePainter {
         // Equipment
         for (e, pos, anim, equipment) in (&*entities_s, &pos_s, &anim_s,
                                           &equipment_s).join() {
+            let pos = pos.interpolate(prev_pos_s.get(e), interpolation.0);
             if let Some(ref equipment) = equipment.body {
                 let tex = atlas.rect_for_anim_sprite(
                     equipment.get_equipment_anim()).unwrap()
@@ -133,55 +145,103 @@ impl<'a> System<'a> for SpritePainter {
     }
 }
 
-/// Paints components with a Pos and Tilemap
-pub struct TilemapPainter {
-    /// If false will re-buffer tilemaps
-    buffered: bool
-}
+/// Paints components with a Pos and Tilemap. Tilemaps are only re-buffered when
+/// TerrainVertexBufferNeedsUpdate is set (i.e. when chunks are loaded or
+/// unloaded).
+/// Tiles are autotiled - if a tile's tileset has transition tiles for it, the
+/// transition tile is picked by looking at the neighbouring tiles (including
+/// those in neighbouring chunks), and the neighbouring terrain is drawn
+/// underneath it.
+pub struct TilemapPainter;
 
 impl TilemapPainter {
-    pub fn new() -> TilemapPainter {
-        TilemapPainter {
-            buffered: false,
-        }
+    /// Get the tile at a given tile offset from the top left of a chunk. The
+    /// offset can go outside of the chunk, in which case the tile is looked up
+    /// in the neighbouring chunk (if it's loaded).
+    fn tile_at(chunks: &HashMap<(i32, i32), &Tilemap>, chunk: (i32, i32),
+               x: i32, y: i32) -> Option<(BiomeType, u8)> {
+        let size = TILEMAP_SIZE as i32;
+        let chunk = (chunk.0 + x.div_euclid(size), chunk.1 + y.div_euclid(size));
+        let (x, y) = (x.rem_euclid(size) as usize, y.rem_euclid(size) as usize);
+        chunks.get(&chunk).map(|tm| (tm.biome, tm.data[x + y * TILEMAP_SIZE]))
     }
 }
 
 impl<'a> System<'a> for TilemapPainter {
     type SystemData = (
         WriteExpect<'a, TerrainVertexBuffer>,
-        WriteExpect<'a, TerrainVertexBufferNeedsUpdate>,
+        ReadExpect<'a, TerrainVertexBufferNeedsUpdate>,
         ReadExpect<'a, TextureAtlas<TextureKey>>,
         ReadStorage<'a, Pos>,
         ReadStorage<'a, Tilemap>);
 
-    fn run(&mut self, (mut vertex_buffer, mut needs_update, atlas, pos_s, tm_s): Self::SystemData) {
-        if self.buffered { return }
-        self.buffered = true;
-        needs_update.0 = true;
+    fn run(&mut self, (mut vertex_buffer, needs_update, atlas, pos_s, tm_s): Self::SystemData) {
+        if !needs_update.0 { return }
 
         let vertex_buffer = &mut vertex_buffer.0;
 
+        // Index the chunks by position so we can look at tiles across chunk
+        // borders, and look up the tileset & palette for each biome once.
+        let chunks : HashMap<(i32, i32), &Tilemap> = (&pos_s, &tm_s).join()
+            .map(|(pos, tm)| ((pos.pos.x as i32, pos.pos.y as i32), tm)).collect();
+        let mut biomes : BTreeMap<BiomeType, (&Tileset, Vec<[u32; 2]>)> = BTreeMap::new();
+        for tm in chunks.values() {
+            biomes.entry(tm.biome).or_insert_with(|| {
+                (atlas.rect_for_tileset(tm.biome.tileset()).unwrap(), tm.biome.palette())
+            });
+        }
+        let tile_pos = |(biome, t): (BiomeType, u8)| -> (&Tileset, [u32; 2]) {
+            let (tileset, ref palette) = biomes[&biome];
+            (tileset, *palette.get(t as usize)
+             .expect(&format!("Tile {} not in the palette of biome {}", t, biome.name())))
+        };
+
         let mut ix = vertex_buffer.size as usize;
-        for (pos, tm) in (&pos_s, &tm_s).join() {
-            let tileset = atlas.rect_for_tileset(tm.tileset.convert_to_tex_key()).unwrap();
-            for x in 0..TILEMAP_SIZE {
-                for y in 0..TILEMAP_SIZE {
-                    let x_pos = pos.pos.x * 32.0 * TILEMAP_SIZE as f32 + x as f32 * 32.0;
-                    let y_pos = pos.pos.y * 32.0 * TILEMAP_SIZE as f32 + y as f32 * 32.0;
-                    // Figure out the tile ix
-                    let (tx, ty) = match tm.tileset {
-                        TilesetEnum::Grass => match tm.data[x + y * TILEMAP_SIZE] {
-                            0 => (5, 1), // Dirt
-                            1 => (1, 1), // Grass
-                            2 => (5, 5), // Water
-                            t => panic!("Tile {} not found", t)
+        'chunks: for (&chunk, _) in chunks.iter() {
+            for x in 0..TILEMAP_SIZE as i32 {
+                for y in 0..TILEMAP_SIZE as i32 {
+                    // Each tile takes up to 2 rects. If the buffer is full,
+                    // stop painting rather than panicking - some terrain will
+                    // be missing, but that's better than crashing.
+                    if ix + 12 > vertex_buffer.v_buf.len() {
+                        eprintln!("Warning: Terrain vertex buffer is full, not all tiles painted");
+                        break 'chunks;
+                    }
+                    let x_pos = (chunk.0 * TILEMAP_SIZE as i32 + x) as f32 * TILE_SIZE;
+                    let y_pos = (chunk.1 * TILEMAP_SIZE as i32 + y) as f32 * TILE_SIZE;
+                    let tile = TilemapPainter::tile_at(&chunks, chunk, x, y).unwrap();
+                    let (tileset, [tx, ty]) = tile_pos(tile);
+
+                    // Figure out which neighbours match. Unloaded neighbours
+                    // are assumed to match, so we don't draw edges at the edge
+                    // of the world.
+                    let mut mask = 0;
+                    let mut under = None;
+                    for &(dx, dy, bit) in &[(0, -1, AUTOTILE_N), (1, 0, AUTOTILE_E),
+                                            (0, 1, AUTOTILE_S), (-1, 0, AUTOTILE_W)] {
+                        match TilemapPainter::tile_at(&chunks, chunk, x + dx, y + dy) {
+                            Some(n) if n != tile => if under.is_none() { under = Some(n) },
+                            _ => mask |= bit,
                         }
-                    };
+                    }
+
+                    // Draw the neighbouring terrain underneath, so it shows
+                    // through the rounded corners of transition tiles.
+                    if let (Some(under), true) = (under, tileset.is_autotiled(tx, ty)) {
+                        let (under_tileset, [ux, uy]) = tile_pos(under);
+                        Renderer::rect(&mut vertex_buffer.v_buf[ix .. ix+6],
+                                       &under_tileset.tile(ux, uy), // UV
+                                       x_pos, y_pos, 0.0, // X, Y, Z
+                                       TILE_SIZE, TILE_SIZE,  // W, H
+                                       [1.0, 1.0, 1.0, 1.0]); // Col
+                        ix += 6;
+                    }
+
+                    let [tx, ty] = tileset.autotile(tx, ty, mask);
                     Renderer::rect(&mut vertex_buffer.v_buf[ix .. ix+6],
                                    &tileset.tile(tx, ty), // UV
                                    x_pos, y_pos, 0.0, // X, Y, Z
-                                   32.0, 32.0,            // W, H
+                                   TILE_SIZE, TILE_SIZE,  // W, H
                                    [1.0, 1.0, 1.0, 1.0]); // Col
                     ix += 6;
                 }
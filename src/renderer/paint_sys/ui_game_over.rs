@@ -0,0 +1,53 @@
+use camera::Camera;
+use specs::*;
+use super::*;
+use UIVertexBuffer;
+
+/// Paints the game over screen while the player is dead
+pub struct GameOverPainter;
+
+const OVERLAY_COLOR : [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const TITLE_COLOR : [f32; 4] = [172.0 / 255.0, 50.0 / 255.0, 50.0 / 255.0, 1.0];
+const TEXT_COLOR : [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+impl<'a> System<'a> for GameOverPainter {
+    type SystemData = (
+        WriteExpect<'a, UIVertexBuffer>,
+        ReadExpect<'a, Camera>,
+        ReadExpect<'a, TextureAtlas<TextureKey>>,
+        ReadStorage<'a, PlayerControlled>,
+        ReadStorage<'a, Dead>);
+
+    fn run(&mut self, (mut vertex_buffer, camera, atlas, pc_s, dead_s): Self::SystemData) {
+        let dead = match (&pc_s, &dead_s).join().next() {
+            Some((_, dead)) => dead,
+            None => return,
+        };
+
+        let vertex_buffer = &mut vertex_buffer.0;
+        let mut ix = vertex_buffer.size as usize;
+
+        // Darken the screen (over the inventory, if it's open)
+        let white = atlas.rect_for_tex(get_asset_by_name("White")).unwrap();
+        Renderer::rect(&mut vertex_buffer.v_buf[ix .. ix+6],
+                       &white,
+                       -camera.w / 2.0, -camera.h / 2.0, -4.0,
+                       camera.w, camera.h,
+                       OVERLAY_COLOR);
+        ix += 6;
+
+        let font = atlas.bitmap_font(get_asset_by_name("FontTiny")).unwrap();
+        // Title, at 12x scale
+        let title = "YOU DIED";
+        ix += Renderer::text(&mut vertex_buffer.v_buf[ix..], font, title,
+                             -Renderer::text_width(title, 36.0) / 2.0, -80.0, -5.0,
+                             36.0, 60.0, TITLE_COLOR, false);
+        // Respawn countdown, at 4x scale
+        let countdown = format!("RESPAWNING IN {}", (dead.time / 1000.0).ceil().max(0.0) as u32);
+        ix += Renderer::text(&mut vertex_buffer.v_buf[ix..], font, &countdown,
+                             -Renderer::text_width(&countdown, 12.0) / 2.0, 20.0, -5.0,
+                             12.0, 20.0, TEXT_COLOR, false);
+
+        vertex_buffer.size = ix as u32;
+    }
+}
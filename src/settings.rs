@@ -0,0 +1,16 @@
+//! Settings the player can change while playing
+
+/// The player's settings. A resource in the ECS world.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// Show floating damage numbers and other combat text
+    pub combat_text: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            combat_text: true,
+        }
+    }
+}
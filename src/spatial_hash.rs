@@ -0,0 +1,117 @@
+//! A uniform grid spatial hash, used as the broad phase for collision. Items
+//! are inserted with their AABB, and can then be looked up by an AABB to get
+//! every item that might overlap it.
+
+use std::collections::HashMap;
+use vec::*;
+
+pub struct SpatialHash<T> {
+    cell_size: f32,
+    /// Cells that have had something inserted, mapped to the items touching
+    /// them. Cell vectors are kept around after a clear so we don't need to
+    /// reallocate them each frame.
+    cells: HashMap<(i32, i32), Vec<T>>,
+}
+
+impl<T: Copy + Ord> SpatialHash<T> {
+    pub fn new(cell_size: f32) -> SpatialHash<T> {
+        debug_assert!(cell_size > 0.0, "Spatial hash cell size must be positive");
+        SpatialHash {
+            cell_size: cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Remove every item
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    /// Get the range of cells (inclusive) covered by an AABB
+    fn cell_range(&self, aabb: [Vec32; 2]) -> ((i32, i32), (i32, i32)) {
+        ((((aabb[0].x / self.cell_size).floor() as i32),
+          ((aabb[0].y / self.cell_size).floor() as i32)),
+         (((aabb[1].x / self.cell_size).floor() as i32),
+          ((aabb[1].y / self.cell_size).floor() as i32)))
+    }
+
+    /// Insert an item into every cell its AABB (top left, bottom right) touches.
+    /// An item that's moved can be inserted again, and will then be found at
+    /// both places.
+    pub fn insert(&mut self, item: T, aabb: [Vec32; 2]) {
+        let (min, max) = self.cell_range(aabb);
+        for y in min.1..max.1+1 {
+            for x in min.0..max.0+1 {
+                self.cells.entry((x, y)).or_insert_with(Vec::new).push(item);
+            }
+        }
+    }
+
+    /// Put every item that shares a cell with the given AABB into `out`. Each
+    /// item is only added once. `out` is cleared first.
+    pub fn query(&self, aabb: [Vec32; 2], out: &mut Vec<T>) {
+        out.clear();
+        let (min, max) = self.cell_range(aabb);
+        for y in min.1..max.1+1 {
+            for x in min.0..max.0+1 {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    out.extend_from_slice(cell);
+                }
+            }
+        }
+        // Items spanning several cells, or inserted more than once, will have
+        // been added more than once
+        out.sort();
+        out.dedup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aabb(x0: f32, y0: f32, x1: f32, y1: f32) -> [Vec32; 2] {
+        [Vec32::new(x0, y0), Vec32::new(x1, y1)]
+    }
+
+    #[test]
+    fn query_finds_nearby_items_once() {
+        let mut hash = SpatialHash::new(10.0);
+        hash.insert(0, aabb(0.0, 0.0, 5.0, 5.0));
+        // Spans 4 cells
+        hash.insert(1, aabb(5.0, 5.0, 15.0, 15.0));
+        hash.insert(2, aabb(-25.0, -25.0, -21.0, -21.0));
+        let mut out = Vec::new();
+        hash.query(aabb(2.0, 2.0, 12.0, 12.0), &mut out);
+        assert_eq!(out, vec![0, 1]);
+        hash.query(aabb(-30.0, -30.0, -22.0, -22.0), &mut out);
+        assert_eq!(out, vec![2]);
+        hash.query(aabb(100.0, 100.0, 101.0, 101.0), &mut out);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn moved_items_are_found_at_both_places_once() {
+        let mut hash = SpatialHash::new(10.0);
+        hash.insert(0, aabb(1.0, 1.0, 2.0, 2.0));
+        hash.insert(0, aabb(3.0, 3.0, 4.0, 4.0));
+        hash.insert(0, aabb(31.0, 1.0, 32.0, 2.0));
+        let mut out = Vec::new();
+        hash.query(aabb(0.0, 0.0, 1.0, 1.0), &mut out);
+        assert_eq!(out, vec![0]);
+        hash.query(aabb(30.0, 0.0, 31.0, 1.0), &mut out);
+        assert_eq!(out, vec![0]);
+    }
+
+    #[test]
+    fn clear_removes_everything() {
+        let mut hash = SpatialHash::new(10.0);
+        hash.insert(0, aabb(0.0, 0.0, 5.0, 5.0));
+        hash.clear();
+        let mut out = Vec::new();
+        hash.query(aabb(0.0, 0.0, 5.0, 5.0), &mut out);
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+//! System for streaming tilemap chunks in and out around the camera
+
+use std::collections::HashMap;
+use specs::*;
+use comp::*;
+use vec::*;
+use worldgen::OverworldGen;
+use biome::BiomeType;
+use {TerrainVertexBufferNeedsUpdate, ChunksChanged};
+use coll_layers::get_coll_filter_with_name;
+
+/// Creates Tilemap chunks around the entity with the FollowCamera component,
+/// and deletes them once they're far enough away. Props and enemies are
+/// spawned from the chunk biome's lists when a chunk is loaded, and deleted
+/// along with the chunk. Radii are in chunks.
+pub struct ChunkManagerSys {
+    gen: OverworldGen,
+    /// Chunks closer than this to the camera's chunk will be loaded
+    load_radius: i32,
+    /// Chunks further than this from the camera's chunk will be unloaded. This
+    /// should be larger than load_radius, so that we don't load / unload the
+    /// same chunks over and over at the boundary.
+    unload_radius: i32,
+    /// Currently loaded chunks, mapped to the tilemap entity and any entities
+    /// spawned along with it
+    loaded: HashMap<(i32, i32), (Entity, Vec<Entity>)>,
+}
+
+impl ChunkManagerSys {
+    pub fn new(gen: OverworldGen, load_radius: i32, unload_radius: i32) -> ChunkManagerSys {
+        debug_assert!(unload_radius > load_radius,
+                      "Chunk unload radius should be larger than the load radius");
+        ChunkManagerSys {
+            gen: gen,
+            load_radius: load_radius,
+            unload_radius: unload_radius,
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// Get the chunk coordinate containing a given world position
+    pub fn chunk_at(pos: Vec32) -> (i32, i32) {
+        let chunk_size = TILE_SIZE * TILEMAP_SIZE as f32;
+        ((pos.x / chunk_size).floor() as i32,
+         (pos.y / chunk_size).floor() as i32)
+    }
+}
+
+impl<'a> System<'a> for ChunkManagerSys {
+    type SystemData = (
+        Read<'a, LazyUpdate>,
+        Entities<'a>,
+        WriteExpect<'a, TerrainVertexBufferNeedsUpdate>,
+        WriteExpect<'a, ChunksChanged>,
+        ReadStorage<'a, FollowCamera>,
+        WriteStorage<'a, Pos>,
+        WriteStorage<'a, Tilemap>);
+
+    fn run(&mut self, (lazy_update, entities_s, mut needs_update, mut chunks_changed,
+                       follow_camera_s, mut pos_s, mut tm_s): Self::SystemData) {
+        let centre = match (&pos_s, &follow_camera_s).join().next() {
+            Some((pos, _)) => ChunkManagerSys::chunk_at(pos.pos),
+            None => return,
+        };
+        let mut changed = false;
+
+        // Unload far away chunks. The tilemap is removed straight away (rather
+        // than on the next maintain) so the painter doesn't draw it this frame.
+        let unload_r2 = self.unload_radius * self.unload_radius;
+        let to_unload : Vec<(i32, i32)> = self.loaded.keys().cloned().filter(|c| {
+            let (dx, dy) = (c.0 - centre.0, c.1 - centre.1);
+            dx * dx + dy * dy > unload_r2
+        }).collect();
+        for c in to_unload {
+            let (e, spawned) = self.loaded.remove(&c).unwrap();
+            tm_s.remove(e);
+            entities_s.delete(e).unwrap();
+            for e in spawned {
+                // Might have been killed already
+                if entities_s.is_alive(e) {
+                    entities_s.delete(e).unwrap();
+                }
+            }
+            changed = true;
+        }
+
+        // Load chunks that have come into range
+        let r = self.load_radius;
+        for dx in -r..r+1 {
+            for dy in -r..r+1 {
+                if dx * dx + dy * dy > r * r { continue }
+                let c = (centre.0 + dx, centre.1 + dy);
+                if self.loaded.contains_key(&c) { continue }
+                let tilemap = self.gen.gen_chunk(c.0, c.1);
+                let props = tilemap.biome.props();
+                let mut spawned : Vec<Entity> = self.gen
+                    .gen_props(c.0, c.1, &tilemap, BiomeType::props).into_iter().map(|(ix, pos)| {
+                        let prop = &props[ix];
+                        let builder = lazy_update.create_entity(&*entities_s)
+                            .with(Pos { pos: pos, z: 0.0 })
+                            .with(StaticSprite { w: prop.width, h: prop.height,
+                                                 sprite: prop.sprite,
+                                                 flags: STATIC_SPRITE_UPRIGHT });
+                        match prop.collision_radius {
+                            Some(r) => {
+                                let builder = builder.with(CollCircle {
+                                    r: r, off: Vec32::zero(),
+                                    flags: COLL_SOLID | COLL_STATIC,
+                                    filter: get_coll_filter_with_name("Terrain") });
+                                match prop.collision_height {
+                                    Some(h) => builder.with(CollHeight { h }).build(),
+                                    None => builder.build(),
+                                }
+                            }
+                            None => builder.build(),
+                        }
+                    }).collect();
+                spawned.extend(self.gen.gen_enemies(c.0, c.1, &tilemap,
+                                                    &tilemap.biome.enemies())
+                    .into_iter().map(|(enemy, pos)| {
+                        enemy.build(lazy_update.create_entity(&*entities_s), pos).build()
+                    }));
+                let e = entities_s.create();
+                pos_s.insert(e, Pos { pos: Vec32::new(c.0 as f32, c.1 as f32), z: 0.0 }).unwrap();
+                tm_s.insert(e, tilemap).unwrap();
+                self.loaded.insert(c, (e, spawned));
+                changed = true;
+            }
+        }
+
+        if changed {
+            needs_update.0 = true;
+        }
+        chunks_changed.0 = changed;
+    }
+}
@@ -9,8 +9,8 @@ use input;
 use specs::*;
 use comp::*;
 use renderer::frame_sets::*;
-use renderer::*;
 use vec::*;
+use item::AttackDetails;
 
 pub struct PlayerControllerSys;
 
@@ -25,33 +25,46 @@ impl<'a> System<'a> for PlayerControllerSys {
         ReadExpect<'a, DeltaTime>,
         Entities<'a>,
         ReadStorage<'a, Pos>,
+        ReadStorage<'a, Knockback>,
+        ReadStorage<'a, Stats>,
+        ReadStorage<'a, Equipment>,
+        ReadStorage<'a, StatusEffects>,
+        ReadStorage<'a, Dead>,
         WriteStorage<'a, Vel>,
         WriteStorage<'a, AnimSprite>,
         WriteStorage<'a, PlayerControlled>);
 
-    fn run(&mut self, (lazy_update, input_state, delta, entities_s,
-                       pos_s, mut vel_s, mut anim_s, mut pc_s): Self::SystemData) {
+    fn run(&mut self, (lazy_update, input_state, delta, entities_s, pos_s, knockback_s,
+                       stats_s, equipment_s, status_s, dead_s, mut vel_s, mut anim_s, mut pc_s):
+           Self::SystemData) {
         use specs::Join;
-        for (pos, vel, anim, pc) in (&pos_s, &mut vel_s, &mut anim_s, &mut pc_s).join() {
-            if pc.state == PlayerState::Default {
+        for (e, pos, vel, anim, pc) in (&*entities_s, &pos_s, &mut vel_s,
+                                        &mut anim_s, &mut pc_s).join() {
+            if dead_s.get(e).is_some() { continue }
+            let status = status_s.get(e);
+            // Can't move while being knocked back or stunned
+            if pc.state == PlayerState::Default && knockback_s.get(e).is_none() &&
+                !status.map_or(false, |s| s.locked_out()) {
+                let move_speed = pc.move_speed *
+                    status.map_or(1.0, |s| s.move_speed_multiplier());
                 let mut anim_change = None;
                 if *input_state.down.get(&input::Command::MoveUp).unwrap() {
-                    vel.vel.y = -pc.move_speed;
+                    vel.vel.y = -move_speed;
                     anim_change = Some(FS_HUMAN_WALK_UP);
                 }
                 else if *input_state.down.get(&input::Command::MoveDown).unwrap() {
-                    vel.vel.y = pc.move_speed;
+                    vel.vel.y = move_speed;
                     anim_change = Some(FS_HUMAN_WALK_DOWN);
                 }
                 else {
                     vel.vel.y = 0.0;
                 }
                 if *input_state.down.get(&input::Command::MoveLeft).unwrap() {
-                    vel.vel.x = -pc.move_speed;
+                    vel.vel.x = -move_speed;
                     anim_change = Some(FS_HUMAN_WALK_LEFT);
                 }
                 else if *input_state.down.get(&input::Command::MoveRight).unwrap() {
-                    vel.vel.x = pc.move_speed;
+                    vel.vel.x = move_speed;
                     anim_change = Some(FS_HUMAN_WALK_RIGHT);
                 }
                 else {
@@ -61,8 +74,12 @@ impl<'a> System<'a> for PlayerControllerSys {
                 if *input_state.pressed.get(&input::Command::Primary).unwrap() {
                     vel.vel.x = 0.0;
                     vel.vel.y = 0.0;
+                    // Attack with the equipped weapon
+                    let attack = equipment_s.get(e).and_then(|eq| eq.weapon)
+                        .and_then(|weapon| weapon.attack())
+                        .unwrap_or_else(AttackDetails::bare_hands);
                     pc.state = PlayerState::Attacking;
-                    pc.attack_time = 125.0;
+                    pc.attack_time = attack.cooldown;
                     // Figure out attack dir
                     let vec = input_state.screen_mouse -
                         Vec32::new(input_state.window_size.0 as f32,
@@ -91,34 +108,12 @@ impl<'a> System<'a> for PlayerControllerSys {
                     }
 
                     // Spawn attack
-                    lazy_update.create_entity(&*entities_s)
-                        .with(Pos {
-                            pos: pos.pos + (vec.nor() * 16.0),
-                            z: 24.0
-                        })
-                        .with(Hurt { damage: 2,
-                                     mask: Hitmask::default_player_attack(),
-                                     flags: 0 })
-                        .with(CollCircle {
-                            r: 40.0,
-                            off: Vec32::zero(),
-                            flags: 0,
-                        })
-                        .with(HurtKnockbackDir {
-                            knockback: match dir {
-                                Direction::Down   => Vec32::new(0.0,   500.0),
-                                Direction::Left   => Vec32::new(-500.0, 0.0),
-                                Direction::Up     => Vec32::new(0.0,   -500.0),
-                                Direction::Right  => Vec32::new(500.0,  0.0),
-                            },
-                            duration: 96.0
-                        })
-                        .with(Lifetime { lifetime: 125.0 })
-                        .with(AnimSprite::new(64.0, 64.0, 25.0, 5,
-                                              get_asset_by_name("SliceAnim"))
-                              .with_flags(ANIM_SPRITE_NO_LOOP))
-                        .with(Rot { angle: vec.angle() })
-                        .build();
+                    let damage_bonus = stats_s.get(e).map_or(0, |s| s.current.damage_bonus);
+                    if vec.len() > 0.0 {
+                        attack.build(lazy_update.create_entity(&*entities_s), pos.pos, vec,
+                                     damage_bonus, Hitmask::default_player_attack())
+                            .build();
+                    }
                 }
                 if let Some(anim_change) = anim_change {
                     anim.set_anim(anim_change, 4, 150.0);
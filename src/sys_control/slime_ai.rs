@@ -2,8 +2,12 @@ use DeltaTime;
 use specs::*;
 use comp::*;
 use vec::*;
+use phys_query::PhysQuery;
+use coll_layers::get_coll_filter_with_name;
 
 const JUMP_SPEED : f32 = 900.0;
+/// Upwards speed of a jump, so the slime visibly hops
+const HOP_SPEED : f32 = 250.0;
 
 pub struct SlimeAISys;
 
@@ -11,25 +15,38 @@ impl<'a> System<'a> for SlimeAISys {
     type SystemData = (
         Entities<'a>,
         ReadExpect<'a, DeltaTime>,
+        ReadExpect<'a, PhysQuery>,
         ReadStorage<'a, Pos>,
         ReadStorage<'a, Alliance>,
+        ReadStorage<'a, StatusEffects>,
+        ReadStorage<'a, Dead>,
+        ReadStorage<'a, Knockback>,
         WriteStorage<'a, Vel>,
+        WriteStorage<'a, VelZ>,
         WriteStorage<'a, AISlime>,
         WriteStorage<'a, AnimSprite>,
     );
 
-    fn run(&mut self, (entities, delta, pos_s, alliance_s,
-                       mut vel_s, mut ai_s, mut anim_s): Self::SystemData) {
+    fn run(&mut self, (entities, delta, query, pos_s, alliance_s, status_s, dead_s,
+                       knockback_s, mut vel_s, mut vel_z_s, mut ai_s, mut anim_s): Self::SystemData) {
         use specs::Join;
+        // Slimes can't see through terrain
+        let terrain_mask = get_coll_filter_with_name("Terrain").layer;
         for (e, pos, alliance, vel, ai, _anim) in (&*entities, &pos_s, &alliance_s,
                                                   &mut vel_s, &mut ai_s, &mut anim_s).join() {
+            let status = status_s.get(e);
+            // Stunned slimes can't do anything
+            if status.map_or(false, |s| s.locked_out()) { continue }
+            // Knockback is an impulse on Vel, so it mustn't be overwritten
+            let knocked_back = knockback_s.get(e).is_some();
             if ai.attack_target.is_none() {
                 // Find targets
                 for (target_e, target_pos, target_alliance) in (&*entities, &pos_s, &alliance_s).join() {
-                    if e == target_e { continue }
+                    if e == target_e || dead_s.get(target_e).is_some() { continue }
                     if alliance.alliance.attacks(&target_alliance.alliance) {
-                        // Check if in range (200 units)
-                        if (target_pos.pos - pos.pos).len() - 200.0 < 0.0 {
+                        // Check if in range (200 units) and in sight
+                        if (target_pos.pos - pos.pos).len() - 200.0 < 0.0 &&
+                            query.line_of_sight(pos.pos, target_pos.pos, terrain_mask) {
                             ai.attack_target = Some(target_e);
                             break;
                         }
@@ -40,17 +57,19 @@ impl<'a> System<'a> for SlimeAISys {
                 }
             }
             else if ai.attack_target.is_some() {
-                let target_pos = pos_s.get(ai.attack_target.unwrap());
-                if target_pos.is_none() {
+                let target = ai.attack_target.unwrap();
+                let target_pos = pos_s.get(target);
+                // Give up on targets that are gone or dead
+                if target_pos.is_none() || dead_s.get(target).is_some() {
                     ai.attack_target = None;
-                    vel.vel = Vec32::zero();
+                    if !knocked_back { vel.vel = Vec32::zero(); }
                     continue;
                 }
                 let target_pos = target_pos.unwrap();
                 // Check if the target is gone (over 300 units)
                 if (target_pos.pos - pos.pos).len() - 300.0 > 0.0 {
                     ai.attack_target = None;
-                    vel.vel = Vec32::zero();
+                    if !knocked_back { vel.vel = Vec32::zero(); }
                     continue;
                 }
                 match ai.state {
@@ -60,26 +79,28 @@ impl<'a> System<'a> for SlimeAISys {
                     }
                     SlimeState::Charging => {
                         ai.charge_time -= delta.0 * 1000.0;
-                        if ai.charge_time < 0.0 {
+                        // Wait for any knockback to finish before jumping
+                        if ai.charge_time < 0.0 && !knocked_back {
                             ai.charge_time = 1200.0;
                             ai.state = SlimeState::Jumping;
+                            if let Some(vel_z) = vel_z_s.get_mut(e) {
+                                vel_z.vel = HOP_SPEED;
+                            }
                             // Set the velocity to jump towards the target
                             if target_pos.pos == pos.pos {
                                 // Just jump without setting vel if we're on top of the target
                                 continue;
                             }
                             let jump_dir = (target_pos.pos - pos.pos).nor();
-                            vel.vel = jump_dir * JUMP_SPEED;
+                            vel.vel = jump_dir * JUMP_SPEED *
+                                status.map_or(1.0, |s| s.move_speed_multiplier());
                         }
                     }
                     SlimeState::Jumping => {
-                        // Damp vel & decrease charge time
-                        vel.vel *= 0.9;
-                        let len = vel.vel.len();
-                        if len != 0.0 { vel.vel *= (len - 1.0) / len; }
+                        // The jump is slowed by the slime's Damping
                         ai.charge_time -= delta.0 * 1000.0;
                         if ai.charge_time < 0.0 {
-                            vel.vel = Vec32::zero();
+                            if !knocked_back { vel.vel = Vec32::zero(); }
                             ai.state = SlimeState::Idle;
                         }
                     }
@@ -7,6 +7,7 @@ use renderer::*;
 use rand::rngs::StdRng;
 use inventory;
 use KilledEntities;
+use coll_layers::get_coll_filter_with_name;
 
 /// System for processing drops on entity death
 pub struct OnDeathDropSys {
@@ -62,20 +63,33 @@ impl<'a> System<'a> for OnDeathDropSys {
                 let y_vel = rng1.gen_range(-1.0, 1.0);
                 let speed = rng1.gen_range(400.0, 500.0);
                 let vel = Vec32::new(x_vel, y_vel).nor() * speed;
+                let vel_z = rng1.gen_range(150.0, 250.0);
 
                 // Spawn
-                let mut builder = lazy_update.create_entity(&*entities)
-                    .with(pos.clone())
-                    .with(Vel { vel })
-                    .with(Pickup { item: inventory::InventoryItem::new(d.item, num) })
-                    .with(CollCircle { r: 8.0, off: Vec32::zero(), flags: 0})
-                    .with(AnimSprite::new(16.0, 16.0, 40.0, 6, get_asset_by_name("GoldCoinAnim")));
-                match d.item.get_in_world_drawable() {
-                    DrawableComponent::Static(c) => builder = builder.with(c),
-                    DrawableComponent::Anim(c) => builder = builder.with(c),
-                }
-                builder.build();
+                build_pickup(lazy_update.create_entity(&*entities), pos.clone(),
+                             inventory::InventoryItem::new(d.item, num), vel, vel_z)
+                    .build();
             })
         }
     }
 }
+
+/// Add the components for an item dropped in-world to an entity builder. The
+/// item flies off with the given velocity and upwards velocity.
+pub fn build_pickup<B: Builder>(builder: B, pos: Pos, item: inventory::InventoryItem,
+                                vel: Vec32, vel_z: f32) -> B {
+    let builder = builder
+        .with(pos)
+        .with(Vel { vel })
+        .with(Damping { drag: 0.0, friction: 600.0 })
+        .with(VelZ { vel: vel_z, gravity: 800.0, bounce: 0.5 })
+        .with(CollHeight { h: 8.0 })
+        .with(Pickup { item: item })
+        .with(CollCircle { r: 8.0, off: Vec32::zero(), flags: 0,
+                           filter: get_coll_filter_with_name("Item") })
+        .with(AnimSprite::new(16.0, 16.0, 40.0, 6, get_asset_by_name("GoldCoinAnim")));
+    match item.item_type.get_in_world_drawable() {
+        DrawableComponent::Static(c) => builder.with(c),
+        DrawableComponent::Anim(c) => builder.with(c),
+    }
+}
@@ -8,18 +8,28 @@ pub struct HealthSys;
 
 impl<'a> System<'a> for HealthSys {
     type SystemData = (
+        Read<'a, LazyUpdate>,
         Entities<'a>,
         WriteExpect<'a, KilledEntities>,
         ReadExpect<'a, DeltaTime>,
         ReadExpect<'a, Collisions>,
         ReadStorage<'a, Hurt>,
         ReadStorage<'a, HurtKnockbackDir>,
+        ReadStorage<'a, HurtStatusEffects>,
+        ReadStorage<'a, Mass>,
+        ReadStorage<'a, Respawn>,
+        ReadStorage<'a, Dead>,
+        ReadStorage<'a, Pos>,
+        WriteStorage<'a, Projectile>,
         WriteStorage<'a, Health>,
         WriteStorage<'a, Tint>,
-        WriteStorage<'a, Knockback>);
+        WriteStorage<'a, Knockback>,
+        WriteStorage<'a, StatusEffects>);
 
-    fn run(&mut self, (entities_s, mut killed, delta, collisions, hurt_s, hurt_knockback_dir_s,
-                       mut health_s, mut tint_s, mut on_hit_s): Self::SystemData) {
+    fn run(&mut self, (lazy_update, entities_s, mut killed, delta, collisions, hurt_s,
+                       hurt_knockback_dir_s, hurt_status_s, mass_s, respawn_s, dead_s, pos_s,
+                       mut projectile_s, mut health_s, mut tint_s, mut on_hit_s,
+                       mut status_s): Self::SystemData) {
 
         for (e, mut health) in (&*entities_s, &mut health_s).join() {
             if health.inv_time > 0.0 {
@@ -34,25 +44,61 @@ impl<'a> System<'a> for HealthSys {
         for (e0, e1, _) in &collisions.0 {
             // if e0 has health and e1 has a hurt, then hurt e0
             if let Some(health) = health_s.get_mut(*e0) {
-                if health.inv_time != 0.0 {continue}
+                // Dead things (waiting to respawn) can't be hurt
+                if health.inv_time != 0.0 || dead_s.get(*e0).is_some() {continue}
                 if let Some(hurt) = hurt_s.get(*e1) {
                     if !health.mask.collides(&hurt.mask) { continue; }
-                    if health.hurt(&hurt) {
-                        entities_s.delete(*e0).unwrap();
+                    // Used up hurts can't hit anything else this tick, and
+                    // projectiles only hit each entity once
+                    if killed.0.contains(e1) ||
+                        projectile_s.get(*e1).map_or(false, |p| p.has_hit(*e0)) { continue; }
+                    let (damage, dies) = health.hurt(&hurt);
+                    if dies {
+                        if respawn_s.get(*e0).is_none() {
+                            entities_s.delete(*e0).unwrap();
+                        }
                         killed.0.push(*e0);
                     }
+                    if let Some(pos) = pos_s.get(*e0) {
+                        let text = if damage > 0 {
+                            CombatText::Damage(damage, hurt.damage_type)
+                        } else if hurt.damage > 0 {
+                            CombatText::Immune
+                        } else {
+                            CombatText::Miss
+                        };
+                        text.build(lazy_update.create_entity(&*entities_s), pos).build();
+                    }
                     health.inv_time = health.max_inv_time;
+                    // Projectiles remember every hit, but only run out of
+                    // pierce if the hurt dies
+                    let used_up = projectile_s.get_mut(*e1).map_or(true, |p| p.hit(*e0));
+                    if hurt.flags & HURT_DIES > 0 && used_up {
+                        entities_s.delete(*e1).unwrap();
+                        killed.0.push(*e1);
+                    }
                     // Apply tint to e0
                     tint_s.insert(*e0, Tint {
                         col: [1.0, 0.1, 0.1, 1.0],
                     }).unwrap();
                     if let Some(kb) = hurt_knockback_dir_s.get(*e1) {
-                        // Apply knockback to e0
+                        // Apply knockback to e0 - heavier entities are
+                        // knocked back less
                         on_hit_s.insert(*e0, Knockback {
-                            knockback: kb.knockback,
+                            knockback: kb.knockback / Mass::of(mass_s.get(*e0)),
                             duration: kb.duration,
                         }).unwrap();
                     }
+                    if let Some(hurt_status) = hurt_status_s.get(*e1) {
+                        // Apply status effects to e0
+                        if status_s.get(*e0).is_none() {
+                            status_s.insert(*e0, StatusEffects::default()).unwrap();
+                        }
+                        let status = status_s.get_mut(*e0).unwrap();
+                        for effect in &hurt_status.effects {
+                            status.apply(*effect);
+                        }
+                    }
                 }
             }
         }
@@ -1,7 +1,10 @@
 use DeltaTime;
 use specs::*;
 use comp::*;
+use vec::*;
 
+/// Applies Knockback impulses to Vel, and removes Knockback once its duration
+/// is over
 pub struct KnockbackSys;
 
 impl<'a> System<'a> for KnockbackSys {
@@ -9,12 +12,13 @@ impl<'a> System<'a> for KnockbackSys {
         Entities<'a>,
         ReadExpect<'a, DeltaTime>,
         WriteStorage<'a, Knockback>,
-        WriteStorage<'a, Pos>);
+        WriteStorage<'a, Vel>);
 
-    fn run(&mut self, (entities_s, delta, mut knockback_s, mut pos_s): Self::SystemData) {
+    fn run(&mut self, (entities_s, delta, mut knockback_s, mut vel_s): Self::SystemData) {
         let mut to_remove = Vec::new();
-        for (e, knockback, pos) in (&*entities_s, &mut knockback_s, &mut pos_s).join() {
-            pos.pos += knockback.knockback * delta.0;
+        for (e, knockback, vel) in (&*entities_s, &mut knockback_s, &mut vel_s).join() {
+            vel.vel += knockback.knockback;
+            knockback.knockback = Vec32::zero();
             knockback.duration -= delta.0 * 1000.0;
             if knockback.duration <= 0.0 {
                 to_remove.push(e)
@@ -7,8 +7,53 @@ use DeltaTime;
 use Collisions;
 use CollisionMeta;
 use vec::*;
+use spatial_hash::SpatialHash;
+
+/// Moves entities by their velocity (and VelZ), then slows down those with
+/// Damping (so a velocity set by a controller this tick is moved by in full). This runs
+/// before the PhysSys instances, which then resolve any collisions at the new
+/// positions.
+pub struct MoveSys;
+
+impl<'a> System<'a> for MoveSys {
+    type SystemData = (
+        ReadExpect<'a, DeltaTime>,
+        ReadStorage<'a, Damping>,
+        WriteStorage<'a, Vel>,
+        WriteStorage<'a, VelZ>,
+        WriteStorage<'a, Pos>,
+    );
+
+    fn run(&mut self, (delta, damping_s, mut vel_s, mut vel_z_s, mut pos_s): Self::SystemData) {
+        use specs::Join;
+
+        for (vel, pos) in (&vel_s, &mut pos_s).join() {
+            pos.pos.x += vel.vel.x * delta.0;
+            pos.pos.y += vel.vel.y * delta.0;
+        }
+        for (damping, vel) in (&damping_s, &mut vel_s).join() {
+            vel.vel = damping.apply(vel.vel, delta.0);
+        }
+        for (vel_z, pos) in (&mut vel_z_s, &mut pos_s).join() {
+            vel_z.step(&mut pos.z, delta.0);
+        }
+    }
+}
+
+/// Size of the cells in the broad phase spatial hash
+const BROAD_PHASE_CELL_SIZE : f32 = 64.0;
 
 pub struct PhysSys<C0: Coll<C1>, C1: Coll<C0>> {
+    /// Broad phase - C1 colliders are put in here each frame, so we only need
+    /// to resolve C0 colliders against those nearby. Colliders moved during
+    /// the pass are inserted again at their new position, so they're never
+    /// missed by later lookups.
+    broad_phase: SpatialHash<Entity>,
+    /// Buffer of entities returned by broad phase queries
+    nearby: Vec<Entity>,
+    /// Velocity changes from bouncing, applied once every collision has been
+    /// seen so both sides of a collision see the same velocities
+    bounces: Vec<(Entity, Vec32)>,
     m0: marker::PhantomData<C0>,
     m1: marker::PhantomData<C1>,
 }
@@ -16,6 +61,9 @@ pub struct PhysSys<C0: Coll<C1>, C1: Coll<C0>> {
 impl<C0: Coll<C1>, C1: Coll<C0>> PhysSys<C0, C1> {
     pub fn new() -> PhysSys<C0, C1> {
         PhysSys {
+            broad_phase: SpatialHash::new(BROAD_PHASE_CELL_SIZE),
+            nearby: Vec::new(),
+            bounces: Vec::new(),
             m0: marker::PhantomData,
             m1: marker::PhantomData,
         }
@@ -24,33 +72,81 @@ impl<C0: Coll<C1>, C1: Coll<C0>> PhysSys<C0, C1> {
 
 impl<'a, C0: Coll<C1> + Component, C1: Coll<C0> + Component> System<'a> for PhysSys<C0, C1> {
     type SystemData = (
-        ReadExpect<'a, DeltaTime>,
         WriteExpect<'a, Collisions>,
-        ReadStorage<'a, Vel>,
         ReadStorage<'a, C0>,
         ReadStorage<'a, C1>,
         Entities<'a>,
+        ReadStorage<'a, PrevPos>,
+        ReadStorage<'a, Mass>,
+        ReadStorage<'a, CollHeight>,
         WriteStorage<'a, Pos>,
+        WriteStorage<'a, Vel>,
     );
 
-    fn run(&mut self, (delta, mut collisions, vel_s, coll0_s,
-                       coll1_s, entities_s, mut pos_s): Self::SystemData) {
+    fn run(&mut self, (mut collisions, coll0_s, coll1_s, entities_s,
+                       prev_pos_s, mass_s, height_s, mut pos_s, mut vel_s): Self::SystemData) {
         use specs::Join;
 
-        for (vel, pos) in (&vel_s, &mut pos_s).join() {
-            pos.pos.x += vel.vel.x * delta.0;
-            pos.pos.y += vel.vel.y * delta.0;
+        // Rebuild the broad phase
+        self.broad_phase.clear();
+        for (e1, pos1, coll1) in (&*entities_s, &pos_s, &coll1_s).join() {
+            self.broad_phase.insert(e1, coll1.aabb(pos1.pos));
+        }
+
+        // Continuous collision detection - stop fast movers just inside the
+        // first static body they'd hit since the start of the tick, so the
+        // narrow phase below records the collision and bounces them off it
+        for (e0, coll0, prev) in (&*entities_s, &coll0_s, &prev_pos_s).join() {
+            let flags0 = coll0.flags();
+            if flags0 & COLL_CCD == 0 || flags0 & COLL_SOLID == 0 ||
+                flags0 & COLL_STATIC > 0 { continue }
+            let (from, to) = match pos_s.get(e0) {
+                Some(pos) => (prev.pos, pos.pos),
+                None => continue,
+            };
+            let (aabb_from, aabb_to) = (coll0.aabb(from), coll0.aabb(to));
+            if (to - from).len() <= ccd_threshold(aabb_from) { continue }
+            let swept = [Vec32::new(aabb_from[0].x.min(aabb_to[0].x),
+                                    aabb_from[0].y.min(aabb_to[0].y)),
+                         Vec32::new(aabb_from[1].x.max(aabb_to[1].x),
+                                    aabb_from[1].y.max(aabb_to[1].y))];
+            self.broad_phase.query(swept, &mut self.nearby);
+            let mut toi = 1.0f32;
+            for &e1 in &self.nearby {
+                if e1 == e0 { continue; }
+                let (pos1, coll1) = (pos_s.get(e1).unwrap(), coll1_s.get(e1).unwrap());
+                if coll1.flags() & (COLL_SOLID | COLL_STATIC) != COLL_SOLID | COLL_STATIC ||
+                    !coll0.filter().collides(&coll1.filter()) ||
+                    !CollHeight::overlaps(prev.z, height_s.get(e0),
+                                          pos1.z, height_s.get(e1)) { continue; }
+                if let Some(t) = coll0.sweep(coll1, from, to, pos1.pos) {
+                    toi = toi.min(t);
+                }
+            }
+            if toi < 1.0 {
+                let pos = ccd_stop(from, to, toi);
+                pos_s.get_mut(e0).unwrap().pos = pos;
+                if let Some(coll1) = coll1_s.get(e0) {
+                    self.broad_phase.insert(e0, coll1.aabb(pos));
+                }
+            }
         }
 
         // Update entities that collide
-       for (e0, coll0) in (&*entities_s, &coll0_s).join() {
+        self.bounces.clear();
+        for (e0, coll0) in (&*entities_s, &coll0_s).join() {
             let flags0 = coll0.flags();
-            // No broad phase, just brute force
-            // TODO: Implement broad-phase collision
+            let filter0 = coll0.filter();
+            let mass0 = mass_s.get(e0);
             let mut res = Vec32::zero();
             if let Some(pos0) = pos_s.get(e0) {
-                for (e1, pos1, coll1) in (&*entities_s, &pos_s, &coll1_s).join() {
+                self.broad_phase.query(coll0.aabb(pos0.pos), &mut self.nearby);
+                for &e1 in &self.nearby {
                     if e1 == e0 { continue; }
+                    let (pos1, coll1) = (pos_s.get(e1).unwrap(), coll1_s.get(e1).unwrap());
+                    if !filter0.collides(&coll1.filter()) ||
+                        !CollHeight::overlaps(pos0.z, height_s.get(e0),
+                                              pos1.z, height_s.get(e1)) { continue; }
                     let this_res = coll0.resolve(coll1, pos0.pos, pos1.pos);
                     if this_res.len() == 0.0 { continue; }
 
@@ -64,17 +160,128 @@ impl<'a, C0: Coll<C1> + Component, C1: Coll<C0> + Component> System<'a> for Phys
                     if flags0 & COLL_SOLID == 0 ||
                         flags0 & COLL_STATIC > 0 ||
                         flags1 & COLL_SOLID == 0 { continue }
-                    if flags1 & COLL_STATIC > 0 {
+                    let is_static1 = flags1 & COLL_STATIC > 0;
+                    if is_static1 {
                         res += this_res;
                     } else {
-                        res += this_res / 2.0;
+                        res += this_res * Mass::resolve_share(mass0, mass_s.get(e1));
+                    }
+                    if let Some(mass0) = mass0 {
+                        let vel0 = vel_s.get(e0).map_or(Vec32::zero(), |v| v.vel);
+                        let (vel1, mass1) = if is_static1 { (Vec32::zero(), None) } else {
+                            (vel_s.get(e1).map_or(Vec32::zero(), |v| v.vel),
+                             Some(Mass::of(mass_s.get(e1))))
+                        };
+                        let bounce = mass0.bounce(vel0, vel1, mass1, this_res.nor());
+                        if bounce.len() > 0.0 { self.bounces.push((e0, bounce)); }
                     }
                 }
             } else { continue }
             // Now add this res to e0's pos component
+            if res.len() == 0.0 { continue }
             let pos = pos_s.get_mut(e0).unwrap();
             pos.pos.x += res.x;
             pos.pos.y += res.y;
+            if let Some(coll1) = coll1_s.get(e0) {
+                self.broad_phase.insert(e0, coll1.aabb(pos.pos));
+            }
         };
+        for &(e, bounce) in &self.bounces {
+            if let Some(vel) = vel_s.get_mut(e) {
+                vel.vel += bounce;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "bench"))]
+mod benches {
+    use super::*;
+    use test::Bencher;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    const NUM_COLLIDERS : usize = 5000;
+    /// Colliders are spread over a square this wide
+    const AREA_SIZE : f32 = 4000.0;
+
+    fn create_world() -> World {
+        let mut world = World::new();
+        world.register::<Pos>();
+        world.register::<PrevPos>();
+        world.register::<Vel>();
+        world.register::<Mass>();
+        world.register::<Damping>();
+        world.register::<VelZ>();
+        world.register::<CollHeight>();
+        world.register::<CollCircle>();
+        world.add_resource(DeltaTime(0.016));
+        world.add_resource(Collisions(Vec::new()));
+        let mut rng = StdRng::from_seed([7; 32]);
+        for _ in 0..NUM_COLLIDERS {
+            world.create_entity()
+                .with(Pos { pos: Vec32::new(rng.gen_range(0.0, AREA_SIZE),
+                                            rng.gen_range(0.0, AREA_SIZE)), z: 0.0 })
+                .with(CollCircle { r: rng.gen_range(8.0, 24.0), off: Vec32::zero(),
+                                   flags: COLL_SOLID, filter: CollFilter::all() })
+                .build();
+        }
+        world
+    }
+
+    /// Put every collider back where it started, so each iteration resolves the
+    /// same overlaps
+    fn reset(world: &World, start: &[(Entity, Vec32)]) {
+        let mut pos_s = world.write_storage::<Pos>();
+        for &(e, pos) in start {
+            pos_s.get_mut(e).unwrap().pos = pos;
+        }
+        world.write_resource::<Collisions>().0.clear();
+    }
+
+    fn start_positions(world: &World) -> Vec<(Entity, Vec32)> {
+        (&*world.entities(), &world.read_storage::<Pos>()).join()
+            .map(|(e, pos)| (e, pos.pos)).collect()
+    }
+
+    #[bench]
+    fn bench_phys_broad_phase(b: &mut Bencher) {
+        let world = create_world();
+        let start = start_positions(&world);
+        let mut sys = PhysSys::<CollCircle, CollCircle>::new();
+        b.iter(|| {
+            reset(&world, &start);
+            sys.run_now(&world.res);
+            world.read_resource::<Collisions>().0.len()
+        });
+    }
+
+    /// The narrow phase on every pair, which is what PhysSys did before it had
+    /// a broad phase. Like PhysSys, this records collisions and moves the
+    /// colliders apart (there's no Mass, so nothing bounces).
+    #[bench]
+    fn bench_phys_brute_force(b: &mut Bencher) {
+        let world = create_world();
+        let start = start_positions(&world);
+        b.iter(|| {
+            reset(&world, &start);
+            let entities_s = world.entities();
+            let coll_s = world.read_storage::<CollCircle>();
+            let mut pos_s = world.write_storage::<Pos>();
+            let mut collisions = world.write_resource::<Collisions>();
+            for (e0, coll0) in (&*entities_s, &coll_s).join() {
+                let mut res = Vec32::zero();
+                let pos0 = pos_s.get(e0).unwrap().pos;
+                for (e1, pos1, coll1) in (&*entities_s, &pos_s, &coll_s).join() {
+                    if e1 == e0 { continue; }
+                    let this_res = coll0.resolve(coll1, pos0, pos1.pos);
+                    if this_res.len() == 0.0 { continue; }
+                    collisions.0.push((e0, e1, CollisionMeta { normal: this_res.nor() }));
+                    res += this_res * Mass::resolve_share(None, None);
+                }
+                pos_s.get_mut(e0).unwrap().pos += res;
+            }
+            collisions.0.len()
+        });
     }
 }
@@ -1,8 +1,6 @@
 //! System for dealing with picking up items
 
 const MAGNETISM_SPEED : f32 = 30.0;
-/// Damping applied each frame
-const PICKUP_DAMPING : f32 = 10.0;
 
 use inventory::Inventory;
 use Collisions;
@@ -20,15 +18,17 @@ impl<'a> System<'a> for PickupSys {
         ReadStorage<'a, Pos>,
         WriteStorage<'a, Vel>,
         ReadStorage<'a, Pickup>,
-        ReadStorage<'a, Collector>);
+        ReadStorage<'a, Collector>,
+        ReadStorage<'a, Dead>);
 
     fn run(&mut self, (entities_s, collisions, mut inventory, pos_s, mut vel_s, pickup_s,
-                       collector_s): Self::SystemData) {
+                       collector_s, dead_s): Self::SystemData) {
         // Check for collisions
         for (e0, e1, _) in &collisions.0 {
-            // if e0 has collector and e1 has pickup, then pick e1 up
+            // if e0 has collector and e1 has pickup, then pick e1 up (unless
+            // e0 is dead)
             match (collector_s.get(*e0), pickup_s.get(*e1)) {
-                (Some(_collector), Some(pickup)) => {
+                (Some(_collector), Some(pickup)) if dead_s.get(*e0).is_none() => {
                     if inventory.add_item(pickup.item) {
                         // Remove the pickup item
                         entities_s.delete(*e1).unwrap();
@@ -41,13 +41,9 @@ impl<'a> System<'a> for PickupSys {
         // Apply 'magnetism'
         // Loop over all pickups and apply vel if possible
         for (_, p_pos, vel) in (&pickup_s, &pos_s, &mut vel_s).join() {
-            // apply pickup damping
-            if vel.vel.x != 0.0 || vel.vel.y != 0.0 {
-                let vel_len = vel.vel.len();
-                vel.vel *= (vel_len - PICKUP_DAMPING) / vel_len;
-            }
             // Loop over all collectors
-            for (collector, c_pos) in (&collector_s, &pos_s).join() {
+            for (c_e, collector, c_pos) in (&*entities_s, &collector_s, &pos_s).join() {
+                if dead_s.get(c_e).is_some() { continue }
                 // Check if in range
                 let vec = p_pos.pos - c_pos.pos;
                 let dis = vec.len();
@@ -0,0 +1,19 @@
+use specs::*;
+use comp::*;
+
+/// Stores the Pos of every entity in PrevPos. This runs at the start of each
+/// tick, before anything moves.
+pub struct PrevPosSys;
+
+impl<'a> System<'a> for PrevPosSys {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Pos>,
+        WriteStorage<'a, PrevPos>);
+
+    fn run(&mut self, (entities, pos_s, mut prev_pos_s): Self::SystemData) {
+        for (e, pos) in (&*entities, &pos_s).join() {
+            prev_pos_s.insert(e, PrevPos { pos: pos.pos, z: pos.z }).unwrap();
+        }
+    }
+}
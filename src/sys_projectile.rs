@@ -0,0 +1,94 @@
+//! Module for projectiles, and the effects spawned when they're used up
+
+use specs::*;
+use comp::*;
+use DeltaTime;
+use Collisions;
+use KilledEntities;
+
+/// Keeps projectiles flying at their speed, and destroys them when they run out
+/// of range, hit terrain (solid tiles or static bodies) or land on the ground.
+/// Destroyed projectiles are added to KilledEntities. This runs after HealthSys,
+/// which uses up projectiles that hit things.
+pub struct ProjectileSys;
+
+impl<'a> System<'a> for ProjectileSys {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, DeltaTime>,
+        ReadExpect<'a, Collisions>,
+        WriteExpect<'a, KilledEntities>,
+        ReadStorage<'a, Tilemap>,
+        ReadStorage<'a, CollCircle>,
+        ReadStorage<'a, CollRect>,
+        ReadStorage<'a, Pos>,
+        ReadStorage<'a, VelZ>,
+        WriteStorage<'a, Vel>,
+        WriteStorage<'a, Projectile>);
+
+    fn run(&mut self, (entities_s, delta, collisions, mut killed, tm_s, circle_s, rect_s,
+                       pos_s, vel_z_s, mut vel_s, mut projectile_s): Self::SystemData) {
+        let is_terrain = |e: Entity| {
+            let static_solid = COLL_SOLID | COLL_STATIC;
+            tm_s.get(e).is_some() ||
+                circle_s.get(e).map_or(false, |c| c.flags & static_solid == static_solid) ||
+                rect_s.get(e).map_or(false, |c| c.flags & static_solid == static_solid)
+        };
+        for (e0, e1, _) in &collisions.0 {
+            if projectile_s.get(*e0).is_none() || killed.0.contains(e0) { continue }
+            if is_terrain(*e1) {
+                entities_s.delete(*e0).unwrap();
+                killed.0.push(*e0);
+            }
+        }
+
+        for (e, projectile, vel, pos) in (&*entities_s, &mut projectile_s, &mut vel_s,
+                                          &pos_s).join() {
+            if killed.0.contains(&e) { continue }
+            if vel.vel.len() > 0.0 {
+                vel.vel = vel.vel.nor() * projectile.speed;
+            }
+            projectile.travelled += projectile.speed * delta.0;
+            let landed = vel_z_s.get(e).map_or(false, |vel_z| pos.z <= 0.0 && vel_z.vel == 0.0);
+            if projectile.travelled >= projectile.range || landed {
+                entities_s.delete(e).unwrap();
+                killed.0.push(e);
+            }
+        }
+    }
+}
+
+/// Spawns the OnImpact effects of killed entities
+pub struct OnImpactSys;
+
+impl<'a> System<'a> for OnImpactSys {
+    type SystemData = (
+        Read<'a, LazyUpdate>,
+        ReadExpect<'a, KilledEntities>,
+        Entities<'a>,
+        ReadStorage<'a, Pos>,
+        ReadStorage<'a, OnImpact>);
+
+    fn run(&mut self, (lazy_update, killed, entities_s, pos_s, on_impact_s): Self::SystemData) {
+        for e in &killed.0 {
+            let (pos, on_impact) = match (pos_s.get(*e), on_impact_s.get(*e)) {
+                (Some(pos), Some(on_impact)) => (pos, on_impact),
+                _ => continue,
+            };
+            let builder = lazy_update.create_entity(&*entities_s);
+            match on_impact.effect {
+                ImpactEffect::Anim { anim, w, h, num_frames, frame_time } => {
+                    builder
+                        .with(Pos { pos: pos.pos, z: pos.z })
+                        .with(AnimSprite::new(w, h, frame_time, num_frames, anim)
+                              .with_flags(ANIM_SPRITE_NO_LOOP))
+                        .with(Lifetime { lifetime: frame_time * num_frames as f32 })
+                        .build();
+                }
+                ImpactEffect::Enemy(enemy) => {
+                    enemy.build(builder, pos.pos).build();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! System for killed entities that come back to life (i.e. the player)
+
+use specs::*;
+use comp::*;
+use vec::*;
+use rand::*;
+use rand::rngs::StdRng;
+use item::ItemType;
+use inventory::{Inventory, InventoryItem};
+use sys_death_drop::build_pickup;
+use sys_trigger::{TriggerEvents, TriggerEventType};
+use {DeltaTime, KilledEntities, SpawnPoint};
+
+/// What the player loses when they die. Some of an item in their inventory
+/// (e.g. money) is dropped where they died, so they can go back for it.
+pub struct DeathPenalty {
+    /// The item that's dropped, or None for no penalty
+    pub item: Option<ItemType>,
+    /// How much of the item is dropped, in percent
+    pub percent: u32,
+}
+
+impl DeathPenalty {
+    /// How many items are dropped, out of how many the player has
+    pub fn amount(&self, count: u32) -> u32 {
+        (count as u64 * self.percent.min(100) as u64 / 100) as u32
+    }
+}
+
+/// Makes killed entities with Respawn Dead, rather than letting them be
+/// deleted, and brings them back to life at the SpawnPoint once their delay is
+/// up. Also moves the SpawnPoint to any Checkpoint the player enters.
+pub struct RespawnSys {
+    rng: StdRng,
+}
+
+impl RespawnSys {
+    pub fn new(rng: StdRng) -> RespawnSys {
+        RespawnSys {
+            rng: rng,
+        }
+    }
+}
+
+impl<'a> System<'a> for RespawnSys {
+    type SystemData = (
+        Read<'a, LazyUpdate>,
+        Entities<'a>,
+        ReadExpect<'a, DeltaTime>,
+        ReadExpect<'a, KilledEntities>,
+        ReadExpect<'a, TriggerEvents>,
+        ReadExpect<'a, DeathPenalty>,
+        WriteExpect<'a, SpawnPoint>,
+        WriteExpect<'a, Inventory>,
+        ReadStorage<'a, Respawn>,
+        ReadStorage<'a, Checkpoint>,
+        ReadStorage<'a, PlayerControlled>,
+        WriteStorage<'a, Dead>,
+        WriteStorage<'a, Pos>,
+        WriteStorage<'a, PrevPos>,
+        WriteStorage<'a, Vel>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, Tint>,
+        WriteStorage<'a, Knockback>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, CollCircle>);
+
+    fn run(&mut self, (lazy_update, entities_s, delta, killed, trigger_events, penalty,
+                       mut spawn_point, mut inventory, respawn_s, checkpoint_s, pc_s,
+                       mut dead_s, mut pos_s, mut prev_pos_s, mut vel_s, mut health_s,
+                       mut tint_s, mut knockback_s, mut status_s,
+                       mut coll_s): Self::SystemData) {
+        // Checkpoints
+        for event in &trigger_events.0 {
+            if event.ty != TriggerEventType::Enter { continue }
+            if checkpoint_s.get(event.trigger).is_none() ||
+                pc_s.get(event.other).is_none() ||
+                dead_s.get(event.other).is_some() { continue }
+            if let Some(pos) = pos_s.get(event.trigger) {
+                spawn_point.0 = pos.pos;
+            }
+        }
+
+        // Count down until dead entities respawn
+        let mut respawned = Vec::new();
+        for (e, dead) in (&*entities_s, &mut dead_s).join() {
+            dead.time -= delta.0 * 1000.0;
+            if dead.time > 0.0 { continue }
+            respawned.push(e);
+            if let Some(health) = health_s.get_mut(e) {
+                health.health = health.max_health;
+                health.inv_time = 0.0;
+            }
+            if let Some(pos) = pos_s.get_mut(e) {
+                pos.pos = spawn_point.0;
+                pos.z = 0.0;
+            }
+            if let (Some(coll), Some(mask)) = (coll_s.get_mut(e), dead.coll_mask) {
+                coll.filter.mask = mask;
+            }
+            prev_pos_s.remove(e);
+            tint_s.remove(e);
+        }
+        for e in respawned {
+            dead_s.remove(e);
+        }
+
+        // Newly killed entities
+        for e in &killed.0 {
+            let respawn = match respawn_s.get(*e) {
+                Some(respawn) => respawn,
+                None => continue,
+            };
+            if dead_s.get(*e).is_some() { continue }
+            // Stop anything bumping into the body
+            let coll_mask = coll_s.get_mut(*e).map(|coll| {
+                let mask = coll.filter.mask;
+                coll.filter.mask = 0;
+                mask
+            });
+            dead_s.insert(*e, Dead { time: respawn.delay, coll_mask: coll_mask }).unwrap();
+            knockback_s.remove(*e);
+            status_s.remove(*e);
+            if let Some(vel) = vel_s.get_mut(*e) {
+                vel.vel = Vec32::zero();
+            }
+
+            // The inventory is the player's, so only they pay the penalty
+            let (item, pos) = match (penalty.item, pos_s.get(*e), pc_s.get(*e)) {
+                (Some(item), Some(pos), Some(_)) => (item, pos),
+                _ => continue,
+            };
+            let amount = penalty.amount(inventory.count(item));
+            let mut num = inventory.remove(item, amount);
+            // Drop them in stacks
+            while num > 0 {
+                let stack = num.min(99);
+                num -= stack;
+                let vel = Vec32::new(self.rng.gen_range(-1.0, 1.0),
+                                     self.rng.gen_range(-1.0, 1.0)).nor()
+                    * self.rng.gen_range(200.0, 300.0);
+                let vel_z = self.rng.gen_range(150.0, 250.0);
+                build_pickup(lazy_update.create_entity(&*entities_s), pos.clone(),
+                             InventoryItem::new(item, stack as u8), vel, vel_z)
+                    .build();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sys_trigger::TriggerEvent;
+
+    fn create_world() -> (World, Entity) {
+        let mut world = World::new();
+        world.register::<Respawn>();
+        world.register::<Checkpoint>();
+        world.register::<PlayerControlled>();
+        world.register::<Dead>();
+        world.register::<Pos>();
+        world.register::<PrevPos>();
+        world.register::<Vel>();
+        world.register::<Health>();
+        world.register::<Tint>();
+        world.register::<Knockback>();
+        world.register::<StatusEffects>();
+        world.register::<CollCircle>();
+        world.add_resource(DeltaTime(0.5));
+        world.add_resource(KilledEntities(Vec::new()));
+        world.add_resource(TriggerEvents(Vec::new()));
+        world.add_resource(DeathPenalty { item: None, percent: 50 });
+        world.add_resource(SpawnPoint(Vec32::new(10.0, 20.0)));
+        world.add_resource(Inventory::new());
+        let player = world.create_entity()
+            .with(Respawn { delay: 1000.0 })
+            .with(PlayerControlled::new())
+            .with(Pos { pos: Vec32::new(100.0, 100.0), z: 0.0 })
+            .with(Vel { vel: Vec32::new(5.0, 0.0) })
+            .with(Health::new(8, Hitmask(HITMASK_PLAYER)))
+            .with(CollCircle { r: 8.0, off: Vec32::zero(), flags: COLL_SOLID,
+                               filter: CollFilter { layer: 1, mask: 3 } })
+            .build();
+        (world, player)
+    }
+
+    fn sys() -> RespawnSys {
+        RespawnSys::new(StdRng::from_seed([0; 32]))
+    }
+
+    #[test]
+    fn killed_player_respawns() {
+        let (world, player) = create_world();
+        let mut sys = sys();
+        world.write_storage::<Health>().get_mut(player).unwrap().health = 0;
+        world.write_resource::<KilledEntities>().0.push(player);
+        sys.run_now(&world.res);
+        world.write_resource::<KilledEntities>().0.clear();
+        assert!(world.read_storage::<Dead>().get(player).is_some());
+        assert_eq!(world.read_storage::<Vel>().get(player).unwrap().vel, Vec32::zero());
+        assert_eq!(world.read_storage::<CollCircle>().get(player).unwrap().filter.mask, 0);
+
+        // Still dead after half the delay
+        sys.run_now(&world.res);
+        assert!(world.read_storage::<Dead>().get(player).is_some());
+        sys.run_now(&world.res);
+        assert!(world.read_storage::<Dead>().get(player).is_none());
+        assert_eq!(world.read_storage::<Health>().get(player).unwrap().health, 8);
+        assert_eq!(world.read_storage::<CollCircle>().get(player).unwrap().filter.mask, 3);
+        assert_eq!(world.read_storage::<Pos>().get(player).unwrap().pos,
+                   Vec32::new(10.0, 20.0));
+    }
+
+    #[test]
+    fn checkpoints_move_spawn_point() {
+        let (mut world, player) = create_world();
+        let checkpoint = world.create_entity()
+            .with(Checkpoint)
+            .with(Pos { pos: Vec32::new(-50.0, 0.0), z: 0.0 })
+            .build();
+        world.write_resource::<TriggerEvents>().0.push(TriggerEvent {
+            ty: TriggerEventType::Enter, trigger: checkpoint, other: player });
+        sys().run_now(&world.res);
+        assert_eq!(world.read_resource::<SpawnPoint>().0, Vec32::new(-50.0, 0.0));
+    }
+
+    #[test]
+    fn penalty_amount() {
+        let penalty = DeathPenalty { item: None, percent: 50 };
+        assert_eq!(penalty.amount(0), 0);
+        assert_eq!(penalty.amount(7), 3);
+        assert_eq!(DeathPenalty { item: None, percent: 150 }.amount(7), 7);
+    }
+}
@@ -0,0 +1,35 @@
+use specs::*;
+use comp::*;
+
+/// Recalculates the Stats of entities when their Equipment changes, and applies
+/// them to their Health and PlayerControlled. Dead entities are left alone until
+/// they respawn, so they aren't brought back to life with some health.
+pub struct StatsSys;
+
+impl<'a> System<'a> for StatsSys {
+    type SystemData = (
+        ReadStorage<'a, Equipment>,
+        ReadStorage<'a, Dead>,
+        WriteStorage<'a, Stats>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, PlayerControlled>,
+        Entities<'a>);
+
+    fn run(&mut self, (equipment_s, dead_s, mut stats_s, mut health_s, mut pc_s,
+                       entities_s): Self::SystemData) {
+        for (e, equipment, stats) in (&*entities_s, &equipment_s, &mut stats_s).join() {
+            if stats.equipment.as_ref() == Some(equipment) ||
+                dead_s.get(e).is_some() { continue }
+            stats.current = equipment.items().iter()
+                .fold(stats.base, |total, item| total + item.stat_modifiers());
+            stats.equipment = Some(equipment.clone());
+
+            if let Some(health) = health_s.get_mut(e) {
+                stats.apply_to_health(health);
+            }
+            if let Some(pc) = pc_s.get_mut(e) {
+                pc.move_speed = stats.current.move_speed.max(0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use specs::*;
+use comp::*;
+use DeltaTime;
+use KilledEntities;
+
+/// Ticks status effects, doing their damage over time and removing them once
+/// they run out. Entities are tinted with the colour of their first effect,
+/// unless they're flashing from being hit.
+pub struct StatusEffectSys;
+
+impl<'a> System<'a> for StatusEffectSys {
+    type SystemData = (
+        Read<'a, LazyUpdate>,
+        Entities<'a>,
+        ReadExpect<'a, DeltaTime>,
+        WriteExpect<'a, KilledEntities>,
+        ReadStorage<'a, Respawn>,
+        ReadStorage<'a, Pos>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, Tint>);
+
+    fn run(&mut self, (lazy_update, entities_s, delta, mut killed, respawn_s, pos_s,
+                       mut status_s, mut health_s, mut tint_s): Self::SystemData) {
+        let mut worn_off = Vec::new();
+        for (e, status) in (&*entities_s, &mut status_s).join() {
+            let mut dies = false;
+            for effect in &mut status.effects {
+                if let Some((damage, damage_type)) = effect.tick(delta.0 * 1000.0) {
+                    if let Some(health) = health_s.get_mut(e) {
+                        let (taken, died) = health.take_damage(damage, damage_type);
+                        dies |= died;
+                        let text = if taken > 0 {
+                            Some(CombatText::Damage(taken, damage_type))
+                        } else if damage > 0 {
+                            Some(CombatText::Immune)
+                        } else {
+                            None
+                        };
+                        if let (Some(text), Some(pos)) = (text, pos_s.get(e)) {
+                            text.build(lazy_update.create_entity(&*entities_s), pos).build();
+                        }
+                    }
+                }
+            }
+            status.effects.retain(|effect| effect.duration > 0.0);
+            if dies && !killed.0.contains(&e) {
+                if respawn_s.get(e).is_none() {
+                    entities_s.delete(e).unwrap();
+                }
+                killed.0.push(e);
+            }
+
+            let flashing = health_s.get(e).map_or(false, |health| health.inv_time > 0.0);
+            match status.effects.first() {
+                Some(effect) if !flashing => {
+                    tint_s.insert(e, Tint { col: effect.ty.tint() }).unwrap();
+                }
+                Some(_) => (),
+                None => {
+                    if !flashing { tint_s.remove(e); }
+                    worn_off.push(e);
+                }
+            }
+        }
+        for e in worn_off {
+            status_s.remove(e);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Module for collision between colliders and the solid tiles of Tilemap
+//! chunks. Which tiles are solid is decided by the chunk's biome palette.
+
+use std::collections::{BTreeMap, HashMap};
+use std::marker;
+use specs::*;
+use comp::*;
+use biome::BiomeType;
+use coll_layers::get_coll_filter_with_name;
+use Collisions;
+use CollisionMeta;
+use vec::*;
+
+/// Resolves colliders of type C against the solid tiles of loaded chunks. Tiles
+/// are on the Terrain collision layer. Each solid tile touched is recorded in
+/// Collisions as a collision between the entity and the chunk's Tilemap entity.
+/// Only colliders with COLL_SOLID are moved (and bounce if they have a Mass
+/// with restitution), and colliders with COLL_STATIC are skipped entirely.
+/// Tiles in unloaded chunks are never solid.
+pub struct TileCollSys<C: Coll<CollRect>> {
+    /// Which tile ids are solid, for every biome seen so far
+    solid_tiles: BTreeMap<BiomeType, Vec<bool>>,
+    /// Collision filter of the tiles
+    tile_filter: CollFilter,
+    m: marker::PhantomData<C>,
+}
+
+impl<C: Coll<CollRect>> TileCollSys<C> {
+    pub fn new() -> TileCollSys<C> {
+        TileCollSys {
+            solid_tiles: BTreeMap::new(),
+            tile_filter: get_coll_filter_with_name("Terrain"),
+            m: marker::PhantomData,
+        }
+    }
+}
+
+/// Get the collider and position of the tile at a given tile position
+pub fn tile_coll(x: i32, y: i32) -> (CollRect, Vec32) {
+    (CollRect { w: TILE_SIZE, h: TILE_SIZE, off: Vec32::zero(),
+                flags: COLL_SOLID | COLL_STATIC, filter: CollFilter::all() },
+     Vec32::new(x as f32 + 0.5, y as f32 + 0.5) * TILE_SIZE)
+}
+
+impl<'a, C: Coll<CollRect> + Component> System<'a> for TileCollSys<C> {
+    type SystemData = (
+        WriteExpect<'a, Collisions>,
+        ReadStorage<'a, Tilemap>,
+        ReadStorage<'a, C>,
+        Entities<'a>,
+        ReadStorage<'a, PrevPos>,
+        ReadStorage<'a, Mass>,
+        WriteStorage<'a, Pos>,
+        WriteStorage<'a, Vel>);
+
+    fn run(&mut self, (mut collisions, tm_s, coll_s, entities_s,
+                       prev_pos_s, mass_s, mut pos_s, mut vel_s): Self::SystemData) {
+        // Index the chunks by position (chunk positions are in chunks)
+        let chunks : HashMap<(i32, i32), (Entity, &Tilemap)> = (&*entities_s, &pos_s, &tm_s)
+            .join().map(|(e, pos, tm)| ((pos.pos.x as i32, pos.pos.y as i32), (e, tm)))
+            .collect();
+        if chunks.is_empty() { return }
+        for tm in chunks.values().map(|(_, tm)| tm) {
+            self.solid_tiles.entry(tm.biome).or_insert_with(|| tm.biome.solid_tiles());
+        }
+        let solid_tiles = &self.solid_tiles;
+        // Get the chunk entity of a tile if the tile is solid
+        let solid_at = |x: i32, y: i32| -> Option<Entity> {
+            let size = TILEMAP_SIZE as i32;
+            let &(e, tm) = chunks.get(&(x.div_euclid(size), y.div_euclid(size)))?;
+            let t = tm.data[(x.rem_euclid(size) + y.rem_euclid(size) * size) as usize];
+            if *solid_tiles[&tm.biome].get(t as usize).unwrap_or(&false) { Some(e) }
+            else { None }
+        };
+
+        let tile_range = |[min, max]: [Vec32; 2]| {
+            (((min.x / TILE_SIZE).floor() as i32, (min.y / TILE_SIZE).floor() as i32),
+             ((max.x / TILE_SIZE).floor() as i32, (max.y / TILE_SIZE).floor() as i32))
+        };
+
+        for (e, coll, pos) in (&*entities_s, &coll_s, &mut pos_s).join() {
+            let flags = coll.flags();
+            if flags & COLL_STATIC > 0 || !coll.filter().collides(&self.tile_filter) {
+                continue
+            }
+
+            // Continuous collision detection - stop fast movers just inside the
+            // first solid tile they'd hit since the start of the tick, so the
+            // resolve below records the collision and bounces them off it
+            if let (Some(prev), true) = (prev_pos_s.get(e),
+                                         flags & COLL_CCD > 0 && flags & COLL_SOLID > 0) {
+                let (from, to) = (prev.pos, pos.pos);
+                if (to - from).len() > ccd_threshold(coll.aabb(from)) {
+                    let (a, b) = (tile_range(coll.aabb(from)), tile_range(coll.aabb(to)));
+                    let mut toi = 1.0f32;
+                    for y in (a.0).1.min((b.0).1)..(a.1).1.max((b.1).1)+1 {
+                        for x in (a.0).0.min((b.0).0)..(a.1).0.max((b.1).0)+1 {
+                            if solid_at(x, y).is_none() { continue }
+                            let (tile, tile_pos) = tile_coll(x, y);
+                            if let Some(t) = coll.sweep(&tile, from, to, tile_pos) {
+                                toi = toi.min(t);
+                            }
+                        }
+                    }
+                    if toi < 1.0 {
+                        pos.pos = ccd_stop(from, to, toi);
+                    }
+                }
+            }
+
+            let ((min_x, min_y), (max_x, max_y)) = tile_range(coll.aabb(pos.pos));
+            for y in min_y..max_y+1 {
+                for x in min_x..max_x+1 {
+                    let chunk_e = match solid_at(x, y) {
+                        Some(chunk_e) => chunk_e,
+                        None => continue,
+                    };
+                    let (tile, tile_pos) = tile_coll(x, y);
+                    let res = coll.resolve(&tile, pos.pos, tile_pos);
+                    if res.len() == 0.0 { continue }
+                    collisions.0.push((e, chunk_e, CollisionMeta {
+                        normal: res.nor()
+                    }));
+                    // Resolve straight away, so the next tile sees the
+                    // corrected position
+                    if flags & COLL_SOLID > 0 {
+                        pos.pos += res;
+                        // Bounce off the tile
+                        if let (Some(mass), Some(vel)) = (mass_s.get(e), vel_s.get_mut(e)) {
+                            vel.vel += mass.bounce(vel.vel, Vec32::zero(), None, res.nor());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_coll_covers_tile() {
+        let (tile, pos) = tile_coll(-1, 2);
+        let [min, max] = Coll::<CollRect>::aabb(&tile, pos);
+        assert_eq!(min, Vec32::new(-TILE_SIZE, TILE_SIZE * 2.0));
+        assert_eq!(max, Vec32::new(0.0, TILE_SIZE * 3.0));
+    }
+}
@@ -0,0 +1,129 @@
+//! System for trigger volumes - publishes events when colliders start or stop
+//! overlapping an entity with a Trigger component.
+
+use std::collections::HashSet;
+use std::mem;
+use specs::*;
+use comp::*;
+use Collisions;
+
+/// Whether an overlap with a trigger has just started, is still going, or has
+/// just ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerEventType {
+    Enter,
+    Stay,
+    Exit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TriggerEvent {
+    pub ty: TriggerEventType,
+    /// The entity with the Trigger component
+    pub trigger: Entity,
+    /// The entity overlapping the trigger. On exit, this might not be alive
+    /// anymore.
+    pub other: Entity,
+}
+
+/// The trigger events since TriggerSys last ran. Systems running after
+/// TriggerSys see this frame's events, and those running before it see the
+/// last frame's.
+pub struct TriggerEvents(pub Vec<TriggerEvent>);
+
+/// Tracks which entities overlap each trigger between frames, using
+/// Collisions.
+pub struct TriggerSys {
+    /// (trigger, other) pairs overlapping last frame
+    overlapping: HashSet<(Entity, Entity)>,
+}
+
+impl TriggerSys {
+    pub fn new() -> TriggerSys {
+        TriggerSys {
+            overlapping: HashSet::new(),
+        }
+    }
+}
+
+impl<'a> System<'a> for TriggerSys {
+    type SystemData = (
+        ReadExpect<'a, Collisions>,
+        WriteExpect<'a, TriggerEvents>,
+        Entities<'a>,
+        ReadStorage<'a, Trigger>);
+
+    fn run(&mut self, (collisions, mut events, entities_s, trigger_s): Self::SystemData) {
+        let mut overlapping = HashSet::new();
+        for &(e0, e1, _) in &collisions.0 {
+            if trigger_s.get(e0).is_some() { overlapping.insert((e0, e1)); }
+            if trigger_s.get(e1).is_some() { overlapping.insert((e1, e0)); }
+        }
+
+        events.0.clear();
+        for &(trigger, other) in &overlapping {
+            events.0.push(TriggerEvent {
+                ty: if self.overlapping.contains(&(trigger, other)) {
+                    TriggerEventType::Stay
+                } else {
+                    TriggerEventType::Enter
+                },
+                trigger, other,
+            });
+        }
+        for &(trigger, other) in self.overlapping.difference(&overlapping) {
+            // Don't bother telling anyone about deleted triggers
+            if !entities_s.is_alive(trigger) { continue }
+            events.0.push(TriggerEvent { ty: TriggerEventType::Exit, trigger, other });
+        }
+        mem::swap(&mut self.overlapping, &mut overlapping);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vec::*;
+    use CollisionMeta;
+
+    fn create_world() -> (World, Entity, Entity) {
+        let mut world = World::new();
+        world.register::<Trigger>();
+        world.add_resource(Collisions(Vec::new()));
+        world.add_resource(TriggerEvents(Vec::new()));
+        let trigger = world.create_entity().with(Trigger).build();
+        let other = world.create_entity().build();
+        (world, trigger, other)
+    }
+
+    fn step(world: &World, sys: &mut TriggerSys, collisions: Vec<(Entity, Entity)>)
+            -> Vec<TriggerEvent> {
+        world.write_resource::<Collisions>().0 = collisions.into_iter()
+            .map(|(a, b)| (a, b, CollisionMeta { normal: Vec32::new(1.0, 0.0) })).collect();
+        sys.run_now(&world.res);
+        world.read_resource::<TriggerEvents>().0.clone()
+    }
+
+    #[test]
+    fn enter_stay_exit() {
+        let (world, trigger, other) = create_world();
+        let mut sys = TriggerSys::new();
+        let event = |ty| TriggerEvent { ty, trigger, other };
+        // Collisions are usually recorded both ways, but only give 1 event
+        assert_eq!(step(&world, &mut sys, vec![(other, trigger), (trigger, other)]),
+                   vec![event(TriggerEventType::Enter)]);
+        assert_eq!(step(&world, &mut sys, vec![(trigger, other)]),
+                   vec![event(TriggerEventType::Stay)]);
+        assert_eq!(step(&world, &mut sys, vec![]),
+                   vec![event(TriggerEventType::Exit)]);
+        assert_eq!(step(&world, &mut sys, vec![]), vec![]);
+    }
+
+    #[test]
+    fn non_trigger_collisions_are_ignored() {
+        let (mut world, _trigger, other) = create_world();
+        let another = world.create_entity().build();
+        let mut sys = TriggerSys::new();
+        assert_eq!(step(&world, &mut sys, vec![(other, another)]), vec![]);
+    }
+}
@@ -0,0 +1,86 @@
+use specs::*;
+use comp::*;
+use vec::*;
+use item::ConsumableEffect;
+use inventory::Inventory;
+use ui::UIState;
+use SpawnPoint;
+
+/// How far from the player consumables spawn enemies
+const SPAWN_DISTANCE : f32 = 32.0;
+
+/// Uses up the consumable item the player used in the inventory UI, running
+/// its effects on the player. Nothing is used up if there's no player, or
+/// they're dead.
+pub struct UseItemSys;
+
+impl<'a> System<'a> for UseItemSys {
+    type SystemData = (
+        Read<'a, LazyUpdate>,
+        Entities<'a>,
+        Read<'a, UIState>,
+        ReadExpect<'a, SpawnPoint>,
+        WriteExpect<'a, Inventory>,
+        ReadStorage<'a, PlayerControlled>,
+        ReadStorage<'a, Dead>,
+        WriteStorage<'a, Pos>,
+        WriteStorage<'a, PrevPos>,
+        WriteStorage<'a, Vel>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, StatusEffects>);
+
+    fn run(&mut self, (lazy_update, entities_s, ui_state, spawn_point, mut inventory,
+                       pc_s, dead_s, mut pos_s, mut prev_pos_s, mut vel_s, mut health_s,
+                       mut status_s): Self::SystemData) {
+        let slot = match ui_state.inventory_state.use_item {
+            Some(slot) => slot,
+            None => return,
+        };
+        let player = match (&*entities_s, &pc_s, &pos_s).join().next() {
+            Some((e, _, _)) if dead_s.get(e).is_none() => e,
+            _ => return,
+        };
+        let effects = match inventory.get_item_type(slot).and_then(|i| i.consumable()) {
+            Some(effects) => effects,
+            None => return,
+        };
+        inventory.take_one(slot);
+
+        for effect in effects {
+            match effect {
+                ConsumableEffect::Heal(amount) => {
+                    if let Some(health) = health_s.get_mut(player) {
+                        let healed = health.heal(amount);
+                        if healed > 0 {
+                            CombatText::Heal(healed).build(
+                                lazy_update.create_entity(&*entities_s),
+                                pos_s.get(player).unwrap()).build();
+                        }
+                    }
+                }
+                ConsumableEffect::StatusEffect(effect) => {
+                    if status_s.get(player).is_none() {
+                        status_s.insert(player, StatusEffects::default()).unwrap();
+                    }
+                    status_s.get_mut(player).unwrap().apply(effect);
+                }
+                ConsumableEffect::TeleportHome => {
+                    let pos = pos_s.get_mut(player).unwrap();
+                    pos.pos = spawn_point.0;
+                    pos.z = 0.0;
+                    // So the player isn't swept through everything in
+                    // between, or drawn sliding across the map
+                    prev_pos_s.remove(player);
+                    if let Some(vel) = vel_s.get_mut(player) {
+                        vel.vel = Vec32::zero();
+                    }
+                }
+                ConsumableEffect::Spawn(enemy) => {
+                    let pos = pos_s.get(player).unwrap().pos +
+                        Vec32::new(0.0, SPAWN_DISTANCE);
+                    enemy.build(lazy_update.create_entity(&*entities_s), pos).build();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Fixed timestep. The simulation is advanced in ticks of a fixed length, so
+//! it behaves the same whatever the frame rate is. Leftover time is carried
+//! over to the next frame, and used to interpolate between the last 2 ticks
+//! when rendering.
+
+pub struct FixedTimestep {
+    /// Length of a tick in seconds
+    tick: f32,
+    /// Time that hasn't been simulated yet, in seconds
+    accumulator: f32,
+    /// The most ticks to run in one frame. If a frame takes longer than this
+    /// many ticks, the rest of the time is dropped (the game slows down rather
+    /// than spending longer and longer catching up).
+    max_ticks: u32,
+}
+
+impl FixedTimestep {
+    pub fn new(tick_rate: f32, max_ticks: u32) -> FixedTimestep {
+        debug_assert!(tick_rate > 0.0, "Tick rate must be positive");
+        FixedTimestep {
+            tick: 1.0 / tick_rate,
+            accumulator: 0.0,
+            max_ticks: max_ticks,
+        }
+    }
+
+    /// Add a frame's worth of time, and get how many ticks to run
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        self.accumulator += frame_time;
+        let mut ticks = 0;
+        while self.accumulator >= self.tick {
+            self.accumulator -= self.tick;
+            ticks += 1;
+            if ticks == self.max_ticks {
+                self.accumulator = self.accumulator.min(self.tick);
+                break;
+            }
+        }
+        ticks
+    }
+
+    /// How far (0 to 1) we are between the last tick and the next one
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.tick).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run for a second at the given frame time, and get the number of ticks
+    fn ticks_in_second(frame_time: f32) -> u32 {
+        let mut timestep = FixedTimestep::new(60.0, 10);
+        let frames = (1.0 / frame_time).round() as u32;
+        (0..frames).map(|_| timestep.advance(frame_time)).sum()
+    }
+
+    #[test]
+    fn tick_count_is_independent_of_frame_rate() {
+        let expected = ticks_in_second(1.0 / 60.0);
+        assert!((expected as i32 - 60).abs() <= 1);
+        for &fps in &[30.0, 144.0, 240.0, 20.0] {
+            assert!((ticks_in_second(1.0 / fps) as i32 - expected as i32).abs() <= 1,
+                    "{} fps gave a different number of ticks", fps);
+        }
+    }
+
+    #[test]
+    fn leftover_time_gives_alpha() {
+        let mut timestep = FixedTimestep::new(8.0, 10);
+        assert_eq!(timestep.advance(0.3125), 2);
+        assert!((timestep.alpha() - 0.5).abs() < 0.001);
+        assert_eq!(timestep.advance(0.0625), 1);
+        assert!(timestep.alpha() < 0.001);
+    }
+
+    #[test]
+    fn slow_frames_are_capped() {
+        let mut timestep = FixedTimestep::new(60.0, 5);
+        assert_eq!(timestep.advance(1.0), 5);
+        assert!(timestep.alpha() <= 1.0);
+        // The dropped time isn't made up for later
+        assert!(timestep.advance(0.0) <= 1);
+    }
+}
@@ -30,6 +30,10 @@ pub struct InventoryState {
     /// Slight misnomer, we don't drag drop, we click to pickup and click to put
     /// down
     pub curr_over: Option<InventorySlotRef>,
+
+    /// If this is Some, the item in this slot was just used (right clicked).
+    /// UseItemSys uses it up on the player.
+    pub use_item: Option<InventorySlotRef>,
 }
 
 /// Called by the UI sys if the inventory is open.
@@ -91,6 +95,12 @@ pub fn process_ui(input_state: &InputState,
                     inventory_state.curr_drag_drop = item;
                 }
         }
+        // Use consumables
+        if *input_state.pressed.get(&Command::Secondary).unwrap() &&
+            inventory_state.curr_drag_drop.is_none() &&
+            inventory.get_item_type(over).map_or(false, |i| i.consumable().is_some()) {
+                inventory_state.use_item = Some(over);
+            }
     }
 
     // Copy over the mouse pos for use in the UI
@@ -6,6 +6,7 @@ mod inventory;
 pub use self::inventory::{InventoryState, InventorySlotRef};
 
 use inventory::Inventory;
+use settings::Settings;
 use camera::Camera;
 use specs::*;
 use input::{*, self};
@@ -26,15 +27,24 @@ impl<'a> System<'a> for UIInputSystem {
     type SystemData = (ReadExpect<'a, InputState>,
                        ReadExpect<'a, Camera>,
                        WriteExpect<'a, Inventory>,
+                       WriteExpect<'a, Settings>,
                        Write<'a, UIState>);
 
-    fn run(&mut self, (input_state, camera, mut inventory,
+    fn run(&mut self, (input_state, camera, mut inventory, mut settings,
                        mut ui_state): Self::SystemData) {
+        // Items are only used on the tick they're clicked
+        ui_state.inventory_state.use_item = None;
+
         // Open / close some UIs
         if *input_state.pressed.get(&input::Command::ToggleInventory).unwrap() {
             ui_state.inventory_open = !ui_state.inventory_open;
         }
 
+        // Settings
+        if *input_state.pressed.get(&input::Command::ToggleCombatText).unwrap() {
+            settings.combat_text = !settings.combat_text;
+        }
+
         // Process UIs that are open
         if ui_state.inventory_open {
             inventory::process_ui(&input_state, camera.w, camera.h,
@@ -18,6 +18,7 @@ impl Vec32 {
     }
     pub fn nor(self) -> Vec32 {self / self.len()}
     pub fn angle(self) -> f32 { self.y.atan2(self.x) }
+    pub fn dot(self, other: Vec32) -> f32 { self.x * other.x + self.y * other.y }
 }
 impl Neg for Vec32 { type Output = Self; fn neg(self) -> Self { Vec32::new(-self.x, -self.y) } }
 impl Add<Vec32> for Vec32 {
@@ -0,0 +1,398 @@
+//! Dungeon generation. Dungeons are built by recursively splitting a rect
+//! (binary space partitioning), placing a room in each leaf, then joining the
+//! rooms of sibling nodes with corridors. Since every split is joined, every
+//! room can be reached from every other room.
+
+use std::collections::VecDeque;
+use comp::*;
+use vec::*;
+use biome::BiomeType;
+use rand::Rng;
+use rand::rngs::StdRng;
+use super::seeded_rng;
+
+/// Dungeon tile ids. These are indices into the palette of dungeon biomes
+/// (see res/biomes/dungeon.yaml).
+pub const TILE_FLOOR : u8 = 0;
+pub const TILE_WALL  : u8 = 1;
+
+/// Leaves are never split smaller than this (in tiles)
+const MIN_LEAF_SIZE : i32 = 8;
+/// Leaves smaller than this might randomly not be split any further, to give
+/// some variety in room size
+const MAX_LEAF_SIZE : i32 = 16;
+/// Chance that a leaf under MAX_LEAF_SIZE isn't split
+const STOP_SPLIT_CHANCE : f32 = 0.25;
+const MIN_ROOM_SIZE : i32 = 4;
+/// Maximum number of enemy spawns per room (Exclusive)
+const MAX_ENEMIES_PER_ROOM : u32 = 4;
+/// Chance for each room to have a chest
+const CHEST_CHANCE : f32 = 0.4;
+
+/// A rect of tiles
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Room {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl Room {
+    pub fn centre(&self) -> (i32, i32) {
+        (self.x + self.w / 2, self.y + self.h / 2)
+    }
+}
+
+/// What a point in the dungeon is for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DungeonPointTag {
+    /// Where the player enters the dungeon. There's exactly 1 of these.
+    Entrance,
+    /// The way out (or further down). There's exactly 1 of these, in the room
+    /// furthest from the entrance (or as far from the entrance as possible, if
+    /// there's only 1 room).
+    Exit,
+    EnemySpawn,
+    Chest,
+}
+
+/// A tagged point in the dungeon, for the game to fill in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DungeonPoint {
+    pub tag: DungeonPointTag,
+    /// World position, where the top left of the dungeon is at (0, 0)
+    pub pos: Vec32,
+}
+
+/// A generated dungeon
+#[derive(Clone, Debug)]
+pub struct Dungeon {
+    /// Width in chunks
+    pub w: usize,
+    /// Height in chunks
+    pub h: usize,
+    /// Tile ids, (w * TILEMAP_SIZE) tiles wide
+    tiles: Vec<u8>,
+    pub rooms: Vec<Room>,
+    pub points: Vec<DungeonPoint>,
+}
+
+impl Dungeon {
+    /// Width in tiles
+    pub fn tile_w(&self) -> i32 { (self.w * TILEMAP_SIZE) as i32 }
+
+    /// Height in tiles
+    pub fn tile_h(&self) -> i32 { (self.h * TILEMAP_SIZE) as i32 }
+
+    /// Get the tile at the given tile position. Anything out of bounds is wall.
+    pub fn tile(&self, x: i32, y: i32) -> u8 {
+        if x < 0 || y < 0 || x >= self.tile_w() || y >= self.tile_h() {
+            TILE_WALL
+        } else {
+            self.tiles[(x + y * self.tile_w()) as usize]
+        }
+    }
+
+    fn set_tile(&mut self, x: i32, y: i32, t: u8) {
+        let w = self.tile_w();
+        self.tiles[(x + y * w) as usize] = t;
+    }
+
+    /// Cut the dungeon up into tilemaps, drawn with the given biome. Chunk
+    /// coordinates start at (0, 0) for the top-left chunk.
+    #[allow(dead_code)]
+    pub fn to_tilemaps(&self, biome: BiomeType) -> Vec<((i32, i32), Tilemap)> {
+        let mut tilemaps = Vec::new();
+        for cy in 0..self.h as i32 {
+            for cx in 0..self.w as i32 {
+                let mut data = [TILE_WALL; TILEMAP_SIZE * TILEMAP_SIZE];
+                for y in 0..TILEMAP_SIZE {
+                    for x in 0..TILEMAP_SIZE {
+                        data[x + y * TILEMAP_SIZE] =
+                            self.tile(cx * TILEMAP_SIZE as i32 + x as i32,
+                                      cy * TILEMAP_SIZE as i32 + y as i32);
+                    }
+                }
+                tilemaps.push(((cx, cy), Tilemap { biome: biome, data: data }));
+            }
+        }
+        tilemaps
+    }
+
+    /// Walking distance (in tiles) from a tile to every other tile, or None if
+    /// a tile can't be reached. Indexed like the tile list.
+    pub fn distances_from(&self, x: i32, y: i32) -> Vec<Option<u32>> {
+        let w = self.tile_w();
+        let mut dis = vec![None; self.tiles.len()];
+        let mut queue = VecDeque::new();
+        if self.tile(x, y) != TILE_FLOOR { return dis }
+        dis[(x + y * w) as usize] = Some(0);
+        queue.push_back((x, y));
+        while let Some((x, y)) = queue.pop_front() {
+            let d = dis[(x + y * w) as usize].unwrap();
+            for &(nx, ny) in &[(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                if self.tile(nx, ny) != TILE_FLOOR { continue }
+                let ix = (nx + ny * w) as usize;
+                if dis[ix].is_none() {
+                    dis[ix] = Some(d + 1);
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        dis
+    }
+}
+
+/// Generates dungeons from a seed
+#[derive(Clone, Copy, Debug)]
+pub struct DungeonGen {
+    pub seed: u64,
+    /// Width in chunks
+    pub w: usize,
+    /// Height in chunks
+    pub h: usize,
+}
+
+// Dungeons aren't entered from the overworld yet
+#[allow(dead_code)]
+impl DungeonGen {
+    pub fn new(seed: u64, w: usize, h: usize) -> DungeonGen {
+        assert!(w > 0 && h > 0, "Dungeons must be at least 1 chunk in size");
+        DungeonGen { seed, w, h }
+    }
+
+    pub fn generate(&self) -> Dungeon {
+        let mut rng = seeded_rng(self.seed);
+        let mut dungeon = Dungeon {
+            w: self.w,
+            h: self.h,
+            tiles: vec![TILE_WALL; self.w * self.h * TILEMAP_SIZE * TILEMAP_SIZE],
+            rooms: Vec::new(),
+            points: Vec::new(),
+        };
+        // Leave a border of wall around the whole dungeon
+        let bounds = Room { x: 1, y: 1,
+                            w: dungeon.tile_w() - 2, h: dungeon.tile_h() - 2 };
+        DungeonGen::split(&mut dungeon, &mut rng, bounds);
+        DungeonGen::place_points(&mut dungeon, &mut rng);
+        dungeon
+    }
+
+    /// Recursively split a leaf, placing rooms and corridors. Returns the index
+    /// of a room in this part of the tree, for the parent to connect to.
+    fn split(dungeon: &mut Dungeon, rng: &mut StdRng, leaf: Room) -> usize {
+        let can_split_x = leaf.w >= MIN_LEAF_SIZE * 2;
+        let can_split_y = leaf.h >= MIN_LEAF_SIZE * 2;
+        let small = leaf.w <= MAX_LEAF_SIZE && leaf.h <= MAX_LEAF_SIZE;
+        if !can_split_x && !can_split_y
+            || small && rng.gen_range(0.0, 1.0) < STOP_SPLIT_CHANCE {
+            return DungeonGen::place_room(dungeon, rng, leaf);
+        }
+
+        // Split the longer side, or a random side if they're similar
+        let split_x = if !can_split_y { true }
+        else if !can_split_x { false }
+        else if leaf.w as f32 > leaf.h as f32 * 1.25 { true }
+        else if leaf.h as f32 > leaf.w as f32 * 1.25 { false }
+        else { rng.gen() };
+        let (a, b) = if split_x {
+            let at = rng.gen_range(MIN_LEAF_SIZE, leaf.w - MIN_LEAF_SIZE + 1);
+            (Room { w: at, .. leaf },
+             Room { x: leaf.x + at, w: leaf.w - at, .. leaf })
+        } else {
+            let at = rng.gen_range(MIN_LEAF_SIZE, leaf.h - MIN_LEAF_SIZE + 1);
+            (Room { h: at, .. leaf },
+             Room { y: leaf.y + at, h: leaf.h - at, .. leaf })
+        };
+        let room_a = DungeonGen::split(dungeon, rng, a);
+        let room_b = DungeonGen::split(dungeon, rng, b);
+        let (from, to) = (dungeon.rooms[room_a].centre(), dungeon.rooms[room_b].centre());
+        DungeonGen::carve_corridor(dungeon, rng, from, to);
+        if rng.gen() { room_a } else { room_b }
+    }
+
+    /// Place a room somewhere inside a leaf, leaving at least 1 tile of wall
+    /// around it. Returns the index of the room.
+    fn place_room(dungeon: &mut Dungeon, rng: &mut StdRng, leaf: Room) -> usize {
+        let max_w = (leaf.w - 2).max(1);
+        let max_h = (leaf.h - 2).max(1);
+        let w = rng.gen_range(MIN_ROOM_SIZE.min(max_w), max_w + 1);
+        let h = rng.gen_range(MIN_ROOM_SIZE.min(max_h), max_h + 1);
+        let x = leaf.x + 1 + rng.gen_range(0, max_w - w + 1);
+        let y = leaf.y + 1 + rng.gen_range(0, max_h - h + 1);
+        let room = Room { x, y, w, h };
+        for ty in y..y+h {
+            for tx in x..x+w {
+                dungeon.set_tile(tx, ty, TILE_FLOOR);
+            }
+        }
+        dungeon.rooms.push(room);
+        dungeon.rooms.len() - 1
+    }
+
+    /// Carve an L-shaped corridor between 2 points
+    fn carve_corridor(dungeon: &mut Dungeon, rng: &mut StdRng,
+                      from: (i32, i32), to: (i32, i32)) {
+        // Which way round the L goes
+        let corner = if rng.gen() { (to.0, from.1) } else { (from.0, to.1) };
+        for &(a, b) in &[(from, corner), (corner, to)] {
+            for x in a.0.min(b.0)..a.0.max(b.0)+1 {
+                for y in a.1.min(b.1)..a.1.max(b.1)+1 {
+                    dungeon.set_tile(x, y, TILE_FLOOR);
+                }
+            }
+        }
+    }
+
+    /// Place the entrance, exit, enemy spawns and chests
+    fn place_points(dungeon: &mut Dungeon, rng: &mut StdRng) {
+        let tile_pos = |(x, y): (i32, i32)|
+            Vec32::new(x as f32 + 0.5, y as f32 + 0.5) * TILE_SIZE;
+        let entrance = dungeon.rooms[0].centre();
+        let dis = dungeon.distances_from(entrance.0, entrance.1);
+        let w = dungeon.tile_w();
+        let exit = if dungeon.rooms.len() > 1 {
+            dungeon.rooms.iter().skip(1).map(|r| r.centre())
+                .max_by_key(|&(x, y)| dis[(x + y * w) as usize]).unwrap()
+        } else {
+            // The furthest tile of the only room
+            let room = dungeon.rooms[0];
+            (room.y..room.y+room.h)
+                .flat_map(|y| (room.x..room.x+room.w).map(move |x| (x, y)))
+                .max_by_key(|&(x, y)| dis[(x + y * w) as usize]).unwrap()
+        };
+
+        let mut used = vec![entrance, exit];
+        let mut points = vec![
+            DungeonPoint { tag: DungeonPointTag::Entrance, pos: tile_pos(entrance) },
+            DungeonPoint { tag: DungeonPointTag::Exit, pos: tile_pos(exit) }];
+        // Enemies and chests go in every room except the entrance room
+        for room in dungeon.rooms.iter().skip(1) {
+            let num_enemies = rng.gen_range(0, MAX_ENEMIES_PER_ROOM);
+            let has_chest = rng.gen_range(0.0, 1.0) < CHEST_CHANCE;
+            let tags = (0..num_enemies).map(|_| DungeonPointTag::EnemySpawn)
+                .chain(if has_chest { Some(DungeonPointTag::Chest) } else { None });
+            for tag in tags {
+                let p = (rng.gen_range(room.x, room.x + room.w),
+                         rng.gen_range(room.y, room.y + room.h));
+                // Just skip it if the spot's taken
+                if used.contains(&p) { continue }
+                used.push(p);
+                points.push(DungeonPoint { tag: tag, pos: tile_pos(p) });
+            }
+        }
+        dungeon.points = points;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile_of(p: &DungeonPoint) -> (i32, i32) {
+        ((p.pos.x / TILE_SIZE) as i32, (p.pos.y / TILE_SIZE) as i32)
+    }
+
+    fn entrance(d: &Dungeon) -> (i32, i32) {
+        tile_of(d.points.iter().find(|p| p.tag == DungeonPointTag::Entrance).unwrap())
+    }
+
+    #[test]
+    fn same_seed_gives_same_dungeon() {
+        let a = DungeonGen::new(77, 3, 2).generate();
+        let b = DungeonGen::new(77, 3, 2).generate();
+        assert_eq!(a.tiles, b.tiles);
+        assert_eq!(a.rooms, b.rooms);
+        assert_eq!(a.points, b.points);
+        let c = DungeonGen::new(78, 3, 2).generate();
+        assert!(a.tiles != c.tiles);
+    }
+
+    #[test]
+    fn every_room_is_reachable_from_the_entrance() {
+        for seed in 0..50 {
+            let d = DungeonGen::new(seed, 4, 4).generate();
+            assert!(d.rooms.len() > 1);
+            let (ex, ey) = entrance(&d);
+            let dis = d.distances_from(ex, ey);
+            for room in &d.rooms {
+                for y in room.y..room.y+room.h {
+                    for x in room.x..room.x+room.w {
+                        assert!(dis[(x + y * d.tile_w()) as usize].is_some(),
+                                "Seed {}: tile ({}, {}) in room {:?} not reachable",
+                                seed, x, y, room);
+                    }
+                }
+            }
+            for p in &d.points {
+                let (x, y) = tile_of(p);
+                assert!(dis[(x + y * d.tile_w()) as usize].is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn points_are_on_floor_with_one_entrance_and_exit() {
+        for seed in 0..20 {
+            let d = DungeonGen::new(seed, 2, 3).generate();
+            let count = |tag| d.points.iter().filter(|p| p.tag == tag).count();
+            assert_eq!(count(DungeonPointTag::Entrance), 1);
+            assert_eq!(count(DungeonPointTag::Exit), 1);
+            for p in &d.points {
+                let (x, y) = tile_of(p);
+                assert_eq!(d.tile(x, y), TILE_FLOOR);
+            }
+        }
+    }
+
+    #[test]
+    fn dungeon_is_walled_in() {
+        let d = DungeonGen::new(5, 3, 3).generate();
+        for x in 0..d.tile_w() {
+            assert_eq!(d.tile(x, 0), TILE_WALL);
+            assert_eq!(d.tile(x, d.tile_h() - 1), TILE_WALL);
+        }
+        for y in 0..d.tile_h() {
+            assert_eq!(d.tile(0, y), TILE_WALL);
+            assert_eq!(d.tile(d.tile_w() - 1, y), TILE_WALL);
+        }
+    }
+
+    #[test]
+    fn tilemaps_match_tiles() {
+        let d = DungeonGen::new(9, 2, 2).generate();
+        let tilemaps = d.to_tilemaps(BiomeType(0));
+        assert_eq!(tilemaps.len(), 4);
+        for ((cx, cy), tm) in tilemaps {
+            for y in 0..TILEMAP_SIZE {
+                for x in 0..TILEMAP_SIZE {
+                    assert_eq!(tm.data[x + y * TILEMAP_SIZE],
+                               d.tile(cx * TILEMAP_SIZE as i32 + x as i32,
+                                      cy * TILEMAP_SIZE as i32 + y as i32));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn one_room_has_separate_entrance_and_exit() {
+        // Too small to split
+        let d = DungeonGen::new(3, 1, 1).generate();
+        assert_eq!(d.rooms.len(), 1);
+        let exit = tile_of(d.points.iter().find(|p| p.tag == DungeonPointTag::Exit).unwrap());
+        assert!(exit != entrance(&d));
+        assert_eq!(d.tile(exit.0, exit.1), TILE_FLOOR);
+    }
+
+    #[test]
+    fn exit_is_not_in_the_entrance_room() {
+        for seed in 0..20 {
+            let d = DungeonGen::new(seed, 3, 3).generate();
+            let exit = d.points.iter().find(|p| p.tag == DungeonPointTag::Exit).unwrap();
+            let (x, y) = tile_of(exit);
+            let r = d.rooms[0];
+            assert!(!(x >= r.x && x < r.x + r.w && y >= r.y && y < r.y + r.h));
+        }
+    }
+}
@@ -0,0 +1,549 @@
+//! Module for procedurally generating the world. Everything here is
+//! deterministic given a seed, so the same seed and chunk will always give the
+//! same tiles.
+
+mod noise;
+mod poisson;
+pub mod dungeon;
+
+use comp::*;
+use vec::*;
+use biome::{BiomeType, BiomeClimate, EnemySpawn, PropDetails};
+use std::collections::BTreeMap;
+use enemies::EnemyType;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+/// Overworld tile ids. These are indices into the palette of overworld biomes
+/// (see res/biomes).
+pub const TILE_DIRT  : u8 = 0;
+pub const TILE_GRASS : u8 = 1;
+pub const TILE_WATER : u8 = 2;
+
+/// The size of the largest elevation features, in tiles
+const ELEVATION_SCALE : f32 = 48.0;
+/// The size of the largest moisture features, in tiles
+const MOISTURE_SCALE : f32 = 32.0;
+/// The size of the largest climate features (which decide biomes), in tiles
+const CLIMATE_SCALE : f32 = 160.0;
+const OCTAVES : u32 = 4;
+/// Layered noise bunches up around 0.5, so the climate is stretched by this
+/// much (around 0.5) to make sure the more extreme biomes get picked too.
+const CLIMATE_CONTRAST : f32 = 2.5;
+/// XOR'd with the world seed to get the seeds of the other noise maps, so
+/// that they aren't all the same map.
+const MOISTURE_SEED_MASK : u64 = 0x5DEE_CE66_D1CE_4E5B;
+const TEMPERATURE_SEED_MASK : u64 = 0x2545_F491_4F6C_DD1D;
+const CLIMATE_MOISTURE_SEED_MASK : u64 = 0x1405_7B7E_F767_814F;
+/// Salts for per-chunk RNGs, so different features of a chunk don't use the
+/// same random numbers.
+const ENEMY_RNG_SALT : u64 = 1;
+/// Salt for the chunk priorities which decide which props are kept when
+/// neighbouring chunks' props are too close
+const PROP_PRIORITY_SALT : u64 = 2;
+/// Each prop type gets its own RNG, salted with this plus the prop's index
+const PROP_RNG_SALT : u64 = 0x100;
+/// Poisson-disk sampling packs roughly this many points into each r*r square,
+/// used to turn a prop density into a spacing.
+const POISSON_PACKING : f32 = 0.7;
+/// Props of different types won't be placed closer than this (in tiles)
+const MIN_PROP_SPACING : f32 = 1.0;
+
+/// Create an RNG from a u64 seed. The same seed always gives the same RNG.
+fn seeded_rng(seed: u64) -> StdRng {
+    let mut bytes = [0u8; 32];
+    for (ix, word) in bytes.chunks_mut(8).enumerate() {
+        let h = noise::hash(seed, ix as i64, 0);
+        for (b, byte) in word.iter_mut().enumerate() {
+            *byte = (h >> (b * 8)) as u8;
+        }
+    }
+    StdRng::from_seed(bytes)
+}
+
+/// A prop that might be placed, used to check props are spaced out
+struct PropPoint<'a> {
+    /// In tiles, relative to the chunk being generated
+    pos: Vec32,
+    /// The spacing of this type of prop, in tiles
+    r: f32,
+    biome: BiomeType,
+    prop_ix: usize,
+    tiles: &'a [u8],
+}
+
+impl<'a> PropPoint<'a> {
+    /// Is this too close to another prop for both to be placed?
+    fn too_close(&self, other: &PropPoint) -> bool {
+        let same_type = if self.biome == other.biome {
+            self.prop_ix == other.prop_ix
+        } else {
+            self.tiles.iter().any(|t| other.tiles.contains(t))
+        };
+        let dis = (self.pos - other.pos).len();
+        dis < MIN_PROP_SPACING || (same_type && dis < self.r.min(other.r))
+    }
+}
+
+/// Generates overworld tilemap chunks from a world seed.
+#[derive(Clone, Debug)]
+pub struct OverworldGen {
+    pub seed: u64,
+    /// The biomes that can be placed in the overworld
+    climates: Vec<BiomeClimate>,
+}
+
+impl OverworldGen {
+    /// # Params
+    /// * `climates` - The overworld biomes. See biome::overworld_climates().
+    pub fn new(seed: u64, climates: Vec<BiomeClimate>) -> OverworldGen {
+        assert!(!climates.is_empty(), "No overworld biomes to generate with");
+        OverworldGen { seed, climates }
+    }
+
+    /// Elevation at a given tile, from 0 to 1
+    pub fn elevation(&self, tile_x: i32, tile_y: i32) -> f32 {
+        noise::fractal_noise(self.seed, tile_x as f32, tile_y as f32,
+                             ELEVATION_SCALE, OCTAVES)
+    }
+
+    /// Moisture at a given tile, from 0 to 1
+    pub fn moisture(&self, tile_x: i32, tile_y: i32) -> f32 {
+        noise::fractal_noise(self.seed ^ MOISTURE_SEED_MASK,
+                             tile_x as f32, tile_y as f32,
+                             MOISTURE_SCALE, OCTAVES)
+    }
+
+    /// The climate (temperature, moisture) at a given tile, each from 0 to 1.
+    /// This varies much more slowly than the tile moisture.
+    pub fn climate(&self, tile_x: i32, tile_y: i32) -> [f32; 2] {
+        let stretch = |n: f32| ((n - 0.5) * CLIMATE_CONTRAST + 0.5).max(0.0).min(1.0);
+        [stretch(noise::fractal_noise(self.seed ^ TEMPERATURE_SEED_MASK,
+                                      tile_x as f32, tile_y as f32, CLIMATE_SCALE, 2)),
+         stretch(noise::fractal_noise(self.seed ^ CLIMATE_MOISTURE_SEED_MASK,
+                                      tile_x as f32, tile_y as f32, CLIMATE_SCALE, 2))]
+    }
+
+    /// Pick the biome for a chunk. This is the biome whose climate is closest
+    /// to the climate at the centre of the chunk.
+    pub fn biome_at(&self, chunk_x: i32, chunk_y: i32) -> BiomeClimate {
+        let half = TILEMAP_SIZE as i32 / 2;
+        let c = self.climate(chunk_x * TILEMAP_SIZE as i32 + half,
+                             chunk_y * TILEMAP_SIZE as i32 + half);
+        let dis2 = |b: &BiomeClimate| {
+            let (dt, dm) = (b.temperature - c[0], b.moisture - c[1]);
+            dt * dt + dm * dm
+        };
+        *self.climates.iter().min_by(|a, b| dis2(a).partial_cmp(&dis2(b)).unwrap())
+            .unwrap()
+    }
+
+    /// Get the tile at a given tile position (in tiles, not world units) for
+    /// the given biome
+    pub fn tile_at(&self, biome: &BiomeClimate, tile_x: i32, tile_y: i32) -> u8 {
+        if self.elevation(tile_x, tile_y) < biome.water_level {
+            TILE_WATER
+        } else if self.moisture(tile_x, tile_y) < biome.ground_moisture {
+            TILE_DIRT
+        } else {
+            TILE_GRASS
+        }
+    }
+
+    /// Generate the tilemap for a given chunk. The chunk coordinate is the same
+    /// as the Pos of the Tilemap entity, i.e. chunk (1, 0) is directly to the
+    /// right of chunk (0, 0).
+    pub fn gen_chunk(&self, chunk_x: i32, chunk_y: i32) -> Tilemap {
+        let biome = self.biome_at(chunk_x, chunk_y);
+        let mut data = [0u8; TILEMAP_SIZE * TILEMAP_SIZE];
+        let base_x = chunk_x * TILEMAP_SIZE as i32;
+        let base_y = chunk_y * TILEMAP_SIZE as i32;
+        for y in 0..TILEMAP_SIZE {
+            for x in 0..TILEMAP_SIZE {
+                data[x + y * TILEMAP_SIZE] =
+                    self.tile_at(&biome, base_x + x as i32, base_y + y as i32);
+            }
+        }
+        Tilemap { biome: biome.biome, data: data }
+    }
+
+    /// Get an RNG for a given chunk. This is seeded from the world seed, the
+    /// chunk, and a salt (so different chunk features get different numbers).
+    fn chunk_rng(&self, chunk_x: i32, chunk_y: i32, salt: u64) -> StdRng {
+        seeded_rng(noise::hash(self.seed.wrapping_add(salt),
+                               chunk_x as i64, chunk_y as i64))
+    }
+
+    /// The Poisson-disk points for one type of prop in a chunk, in tiles
+    /// relative to the chunk, before any are thrown away
+    fn prop_candidates(&self, chunk_x: i32, chunk_y: i32, prop_ix: usize, r: f32)
+                       -> Vec<Vec32> {
+        let size = TILEMAP_SIZE as f32;
+        let mut rng = self.chunk_rng(chunk_x, chunk_y, PROP_RNG_SALT + prop_ix as u64);
+        poisson::poisson_disk(&mut rng, size, size, r)
+    }
+
+    /// When props in neighbouring chunks are too close, the one in the chunk
+    /// with the highest priority is kept. The chunk coordinates break ties.
+    fn prop_priority(&self, chunk_x: i32, chunk_y: i32) -> (u64, i32, i32) {
+        (noise::hash(self.seed.wrapping_add(PROP_PRIORITY_SALT),
+                     chunk_x as i64, chunk_y as i64), chunk_x, chunk_y)
+    }
+
+    /// Scatter props over a chunk. Each prop type is placed with Poisson-disk
+    /// sampling, with a spacing worked out from its density, and is only placed
+    /// on the tiles it's allowed on. Props of different types are kept
+    /// MIN_PROP_SPACING apart.
+    /// So that props in neighbouring chunks don't end up too close, the
+    /// neighbours' points are regenerated, and any point too close to one in a
+    /// chunk with a higher priority is thrown away. Across biome borders, props
+    /// that share a tile count as the same type, and are kept the smaller of
+    /// their spacings apart.
+    /// # Params
+    /// * `tilemap` - The tilemap of this chunk (from gen_chunk)
+    /// * `props_of` - Gets the props of a biome (see BiomeType::props)
+    /// # Returns
+    /// A list of indices into the props of the chunk's biome, and the world
+    /// positions to place them
+    pub fn gen_props<F>(&self, chunk_x: i32, chunk_y: i32, tilemap: &Tilemap,
+                        props_of: F) -> Vec<(usize, Vec32)>
+        where F: Fn(BiomeType) -> Vec<PropDetails> {
+        let size = TILEMAP_SIZE as f32;
+        let spacing = |prop: &PropDetails| (POISSON_PACKING / prop.density).sqrt();
+        let props = props_of(tilemap.biome);
+        let mut biome_props = BTreeMap::new();
+        biome_props.insert(tilemap.biome, props.clone());
+
+        // Find the neighbouring chunks which win over this one. Sparse props
+        // can be spaced more than a chunk apart, so this may look further
+        // than the adjacent chunks.
+        let priority = self.prop_priority(chunk_x, chunk_y);
+        let reach = props.iter().filter(|prop| prop.density > 0.0)
+            .map(|prop| (spacing(prop) / size).ceil() as i32)
+            .max().unwrap_or(0).max(1);
+        let mut neighbours = Vec::new();
+        for nx in chunk_x - reach ..= chunk_x + reach {
+            for ny in chunk_y - reach ..= chunk_y + reach {
+                if self.prop_priority(nx, ny) <= priority { continue }
+                let biome = self.biome_at(nx, ny);
+                biome_props.entry(biome.biome).or_insert_with(|| props_of(biome.biome));
+                neighbours.push((nx, ny, biome));
+            }
+        }
+
+        // Then the points in them that ours have to stay away from
+        let mut rivals = Vec::new();
+        for (nx, ny, biome) in neighbours {
+            let offset = Vec32::new((nx - chunk_x) as f32, (ny - chunk_y) as f32) * size;
+            for (prop_ix, prop) in biome_props[&biome.biome].iter().enumerate() {
+                if prop.density <= 0.0 { continue }
+                let r = spacing(prop);
+                for p in self.prop_candidates(nx, ny, prop_ix, r) {
+                    let t = self.tile_at(&biome,
+                                         nx * TILEMAP_SIZE as i32 + p.x as i32,
+                                         ny * TILEMAP_SIZE as i32 + p.y as i32);
+                    if !prop.tiles.contains(&t) { continue }
+                    rivals.push(PropPoint { pos: offset + p, r: r, biome: biome.biome,
+                                            prop_ix: prop_ix, tiles: &prop.tiles });
+                }
+            }
+        }
+
+        // Props placed in this chunk are checked the same way, with earlier
+        // types of prop winning
+        let mut placed : Vec<PropPoint> = Vec::new();
+        for (prop_ix, prop) in props.iter().enumerate() {
+            if prop.density <= 0.0 { continue }
+            let r = spacing(prop);
+            for p in self.prop_candidates(chunk_x, chunk_y, prop_ix, r) {
+                // p is in tiles, relative to the chunk
+                let t = tilemap.data[p.x as usize + p.y as usize * TILEMAP_SIZE];
+                if !prop.tiles.contains(&t) { continue }
+                let point = PropPoint { pos: p, r: r, biome: tilemap.biome,
+                                        prop_ix: prop_ix, tiles: &prop.tiles };
+                if rivals.iter().chain(placed.iter()).any(|other| point.too_close(other)) {
+                    continue
+                }
+                placed.push(point);
+            }
+        }
+        let chunk_pos = Vec32::new(chunk_x as f32, chunk_y as f32) * size;
+        placed.into_iter().map(|p| (p.prop_ix, (chunk_pos + p.pos) * TILE_SIZE)).collect()
+    }
+
+    /// Decide which enemies to spawn in a chunk, and where. Enemies will never
+    /// be placed on water.
+    /// # Params
+    /// * `tilemap` - The tilemap of this chunk (from gen_chunk)
+    /// * `spawns` - The enemy spawns of the chunk's biome
+    /// # Returns
+    /// A list of enemies and their world positions
+    pub fn gen_enemies(&self, chunk_x: i32, chunk_y: i32, tilemap: &Tilemap,
+                       spawns: &[EnemySpawn]) -> Vec<(EnemyType, Vec32)> {
+        let mut rng = self.chunk_rng(chunk_x, chunk_y, ENEMY_RNG_SALT);
+        let land : Vec<usize> = (0..TILEMAP_SIZE * TILEMAP_SIZE)
+            .filter(|ix| tilemap.data[*ix] != TILE_WATER).collect();
+        let mut enemies = Vec::new();
+        if land.is_empty() { return enemies }
+        let chunk_pos = Vec32::new(chunk_x as f32, chunk_y as f32)
+            * TILEMAP_SIZE as f32 * TILE_SIZE;
+        for spawn in spawns {
+            if rng.gen_range(0.0, 1.0) >= spawn.chance { continue }
+            let num = if spawn.max > spawn.min {
+                rng.gen_range(spawn.min, spawn.max)
+            } else { spawn.min };
+            for _ in 0..num {
+                let ix = land[rng.gen_range(0, land.len())];
+                let tile = Vec32::new((ix % TILEMAP_SIZE) as f32 + 0.5,
+                                      (ix / TILEMAP_SIZE) as f32 + 0.5);
+                enemies.push((spawn.enemy, chunk_pos + tile * TILE_SIZE));
+            }
+        }
+        enemies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use biome::BiomeType;
+
+    /// A couple of made-up biomes, so we don't need to load any assets
+    fn test_climates() -> Vec<BiomeClimate> {
+        vec![BiomeClimate { biome: BiomeType(0), temperature: 0.5, moisture: 0.5,
+                            water_level: 0.38, ground_moisture: 0.4 },
+             BiomeClimate { biome: BiomeType(1), temperature: 0.9, moisture: 0.1,
+                            water_level: 0.2, ground_moisture: 0.6 },
+             BiomeClimate { biome: BiomeType(2), temperature: 0.1, moisture: 0.9,
+                            water_level: 0.5, ground_moisture: 0.3 }]
+    }
+
+    fn test_gen(seed: u64) -> OverworldGen {
+        OverworldGen::new(seed, test_climates())
+    }
+
+    #[test]
+    fn same_seed_and_chunk_gives_same_data() {
+        let gen = test_gen(1234);
+        for &(cx, cy) in &[(0, 0), (3, -2), (-17, 40)] {
+            let a = gen.gen_chunk(cx, cy);
+            let b = test_gen(1234).gen_chunk(cx, cy);
+            assert!(a.data[..] == b.data[..], "Chunk ({}, {}) differs", cx, cy);
+            assert_eq!(a.biome, b.biome);
+        }
+    }
+
+    #[test]
+    fn generation_order_does_not_matter() {
+        let gen = test_gen(99);
+        let first = gen.gen_chunk(5, 5);
+        for cx in -3..3 {
+            for cy in -3..3 {
+                gen.gen_chunk(cx, cy);
+            }
+        }
+        assert!(first.data[..] == gen.gen_chunk(5, 5).data[..]);
+    }
+
+    #[test]
+    fn different_seeds_give_different_worlds() {
+        let a = test_gen(1);
+        let b = test_gen(2);
+        let differs = (0..4).any(|c| a.gen_chunk(c, 0).data[..] != b.gen_chunk(c, 0).data[..]);
+        assert!(differs);
+    }
+
+    #[test]
+    fn only_valid_tiles_generated() {
+        let gen = test_gen(42);
+        for cx in -4..4 {
+            for cy in -4..4 {
+                for t in gen.gen_chunk(cx, cy).data.iter() {
+                    assert!(*t == TILE_DIRT || *t == TILE_GRASS || *t == TILE_WATER);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chunk_matches_tile_at() {
+        let gen = test_gen(7);
+        let chunk = gen.gen_chunk(-1, 2);
+        let biome = gen.biome_at(-1, 2);
+        for y in 0..TILEMAP_SIZE {
+            for x in 0..TILEMAP_SIZE {
+                let (tx, ty) = (-(TILEMAP_SIZE as i32) + x as i32,
+                                2 * TILEMAP_SIZE as i32 + y as i32);
+                assert_eq!(chunk.data[x + y * TILEMAP_SIZE], gen.tile_at(&biome, tx, ty));
+            }
+        }
+    }
+
+    #[test]
+    fn biome_is_closest_climate() {
+        let gen = test_gen(3);
+        for cx in -10..10 {
+            let biome = gen.biome_at(cx, 0);
+            let half = TILEMAP_SIZE as i32 / 2;
+            let c = gen.climate(cx * TILEMAP_SIZE as i32 + half, half);
+            let dis2 = |b: &BiomeClimate| (b.temperature - c[0]).powi(2) + (b.moisture - c[1]).powi(2);
+            for other in test_climates() {
+                assert!(dis2(&biome) <= dis2(&other));
+            }
+        }
+    }
+
+    #[test]
+    fn only_one_biome_means_every_chunk_has_it() {
+        let gen = OverworldGen::new(5, vec![test_climates()[1]]);
+        for cx in -5..5 {
+            assert_eq!(gen.gen_chunk(cx, cx).biome, BiomeType(1));
+        }
+    }
+
+    fn test_props() -> Vec<PropDetails> {
+        vec![PropDetails { sprite: 0, width: 64.0, height: 128.0,
+                           collision_radius: Some(12.0), collision_height: None, density: 0.05,
+                           tiles: vec![TILE_GRASS] },
+             PropDetails { sprite: 1, width: 16.0, height: 16.0,
+                           collision_radius: None, collision_height: None, density: 0.1,
+                           tiles: vec![TILE_GRASS, TILE_DIRT] }]
+    }
+
+    #[test]
+    fn props_are_deterministic_and_follow_tile_rules() {
+        let gen = test_gen(21);
+        let props = test_props();
+        let mut total = 0;
+        for cx in -3..3 {
+            let chunk = gen.gen_chunk(cx, -2);
+            let a = gen.gen_props(cx, -2, &chunk, |_| props.clone());
+            assert_eq!(a, gen.gen_props(cx, -2, &test_gen(21).gen_chunk(cx, -2), |_| props.clone()));
+            total += a.len();
+            for (prop_ix, pos) in a {
+                let tx = (pos.x / TILE_SIZE).floor() as i32 - cx * TILEMAP_SIZE as i32;
+                let ty = (pos.y / TILE_SIZE).floor() as i32 + 2 * TILEMAP_SIZE as i32;
+                let t = chunk.data[tx as usize + ty as usize * TILEMAP_SIZE];
+                assert!(props[prop_ix].tiles.contains(&t));
+            }
+        }
+        assert!(total > 0);
+    }
+
+    #[test]
+    fn props_are_spaced_across_chunk_borders() {
+        let gen = OverworldGen::new(8, vec![test_climates()[0]]);
+        let props = [test_props()[0].clone()];
+        let r = (POISSON_PACKING / props[0].density).sqrt() * TILE_SIZE;
+        let mut all = Vec::new();
+        for cx in 0..3 {
+            for cy in 0..3 {
+                let chunk = gen.gen_chunk(cx, cy);
+                all.extend(gen.gen_props(cx, cy, &chunk, |_| props.to_vec())
+                           .into_iter().map(|(_, p)| p));
+            }
+        }
+        for (ix, a) in all.iter().enumerate() {
+            for b in &all[ix+1..] {
+                assert!((*a - *b).len() >= r - 0.001);
+            }
+        }
+    }
+
+    #[test]
+    fn props_are_spaced_across_biome_borders() {
+        let gen = test_gen(4);
+        // Each biome has different props, but some of them share tiles, so
+        // count as the same type across the border
+        let props_of = |biome: BiomeType| if biome == BiomeType(0) {
+            vec![prop_anywhere(0.05)]
+        } else {
+            vec![test_props()[1].clone(), prop_anywhere(0.02)]
+        };
+        let spacing = |prop: &PropDetails| (POISSON_PACKING / prop.density).sqrt() * TILE_SIZE;
+        // Look around a biome border
+        let border = (0..100).find(|cx| gen.biome_at(*cx, 0).biome != gen.biome_at(cx + 1, 0).biome)
+            .expect("No biome border found");
+        let mut all = Vec::new();
+        for cx in border - 2 .. border + 3 {
+            for cy in -2..3 {
+                let chunk = gen.gen_chunk(cx, cy);
+                let props = props_of(chunk.biome);
+                all.extend(gen.gen_props(cx, cy, &chunk, props_of).into_iter()
+                           .map(|(ix, p)| (chunk.biome, ix, props[ix].clone(), p)));
+            }
+        }
+        for (ix, (biome_a, ix_a, prop_a, a)) in all.iter().enumerate() {
+            for (biome_b, ix_b, prop_b, b) in &all[ix+1..] {
+                let dis = (*a - *b).len();
+                assert!(dis >= MIN_PROP_SPACING * TILE_SIZE - 0.001);
+                let same_type = if biome_a == biome_b { ix_a == ix_b } else {
+                    prop_a.tiles.iter().any(|t| prop_b.tiles.contains(t))
+                };
+                if same_type {
+                    assert!(dis >= spacing(prop_a).min(spacing(prop_b)) - 0.001);
+                }
+            }
+        }
+    }
+
+    /// Props that can go anywhere, so only the spacing decides where they go
+    fn prop_anywhere(density: f32) -> PropDetails {
+        PropDetails { sprite: 0, width: 16.0, height: 16.0,
+                      collision_radius: None, collision_height: None, density: density,
+                      tiles: vec![TILE_DIRT, TILE_GRASS, TILE_WATER] }
+    }
+
+    #[test]
+    fn props_reach_chunk_edges() {
+        let gen = OverworldGen::new(13, vec![test_climates()[0]]);
+        let props = [prop_anywhere(0.05)];
+        let chunk_size = TILEMAP_SIZE as f32 * TILE_SIZE;
+        let mut near_edge = 0;
+        for cx in 0..4 {
+            for cy in 0..4 {
+                let chunk = gen.gen_chunk(cx, cy);
+                for (_, pos) in gen.gen_props(cx, cy, &chunk, |_| props.to_vec()) {
+                    let x = pos.x - cx as f32 * chunk_size;
+                    let y = pos.y - cy as f32 * chunk_size;
+                    let edge = x.min(y).min(chunk_size - x).min(chunk_size - y);
+                    if edge < TILE_SIZE { near_edge += 1; }
+                }
+            }
+        }
+        assert!(near_edge > 0);
+    }
+
+    #[test]
+    fn sparse_props_still_appear() {
+        let gen = OverworldGen::new(17, vec![test_climates()[0]]);
+        // Spaced out more than a chunk apart
+        let props = [prop_anywhere(0.001)];
+        let mut total = 0;
+        for cx in 0..8 {
+            for cy in 0..8 {
+                let chunk = gen.gen_chunk(cx, cy);
+                total += gen.gen_props(cx, cy, &chunk, |_| props.to_vec()).len();
+            }
+        }
+        assert!(total > 0);
+    }
+
+    #[test]
+    fn enemies_are_deterministic_and_on_land() {
+        let gen = test_gen(11);
+        let spawns = [EnemySpawn { enemy: EnemyType::Slime, chance: 1.0, min: 2, max: 5 }];
+        for cx in -3..3 {
+            let chunk = gen.gen_chunk(cx, 1);
+            let a = gen.gen_enemies(cx, 1, &chunk, &spawns);
+            let b = gen.gen_enemies(cx, 1, &chunk, &spawns);
+            assert_eq!(a, b);
+            for (_, pos) in a {
+                let tx = (pos.x / TILE_SIZE).floor() as i32 - cx * TILEMAP_SIZE as i32;
+                let ty = (pos.y / TILE_SIZE).floor() as i32 - TILEMAP_SIZE as i32;
+                assert!(tx >= 0 && tx < TILEMAP_SIZE as i32 && ty >= 0 && ty < TILEMAP_SIZE as i32);
+                assert!(chunk.data[tx as usize + ty as usize * TILEMAP_SIZE] != TILE_WATER);
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Seeded, stateless value noise. Everything here is a pure function of the
+//! seed and the sample position, so chunks can be generated in any order (or
+//! regenerated later) and will always line up.
+
+/// Hash a lattice point with a seed into a u64. This is a splitmix64-style
+/// finaliser, which is plenty good enough for terrain.
+pub fn hash(seed: u64, x: i64, y: i64) -> u64 {
+    let mut h = seed
+        ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    h ^ (h >> 31)
+}
+
+/// Get a value from 0 to 1 for the given lattice point.
+fn lattice(seed: u64, x: i64, y: i64) -> f32 {
+    (hash(seed, x, y) >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Smoothstep, used to interpolate between lattice points
+#[inline]
+fn fade(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[inline]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Sample value noise at a given point. Returns a value from 0 to 1.
+pub fn value_noise(seed: u64, x: f32, y: f32) -> f32 {
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let (tx, ty) = (fade(x - x0), fade(y - y0));
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let top = lerp(lattice(seed, x0, y0), lattice(seed, x0 + 1, y0), tx);
+    let bottom = lerp(lattice(seed, x0, y0 + 1), lattice(seed, x0 + 1, y0 + 1), tx);
+    lerp(top, bottom, ty)
+}
+
+/// Layered (fractal) value noise. Each octave doubles the frequency and halves
+/// the amplitude. Returns a value from 0 to 1.
+/// # Params
+/// * `scale` - The size of a feature of the first octave, in sample units
+/// * `octaves` - The number of layers of noise to add together
+pub fn fractal_noise(seed: u64, x: f32, y: f32, scale: f32, octaves: u32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut max = 0.0;
+    let mut freq = 1.0 / scale;
+    for octave in 0..octaves {
+        // Offset each octave's seed so the layers aren't correlated
+        let octave_seed = seed.wrapping_add((octave as u64).wrapping_mul(0x632B_E59B_D9B4_E019));
+        total += value_noise(octave_seed, x * freq, y * freq) * amplitude;
+        max += amplitude;
+        amplitude /= 2.0;
+        freq *= 2.0;
+    }
+    total / max
+}
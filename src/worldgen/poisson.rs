@@ -0,0 +1,85 @@
+//! Poisson-disk sampling (Bridson's algorithm), for scattering things evenly
+//! but randomly.
+
+use rand::Rng;
+use vec::*;
+
+/// How many candidates to try around each point before giving up on it
+const MAX_ATTEMPTS : usize = 30;
+
+/// Generate a set of points in the rect (0, 0, w, h) where no two points are
+/// closer than `r` to each other.
+pub fn poisson_disk<R: Rng>(rng: &mut R, w: f32, h: f32, r: f32) -> Vec<Vec32> {
+    // Background grid, each cell can contain at most 1 point
+    let cell = r / 2f32.sqrt();
+    let grid_w = (w / cell).ceil() as usize + 1;
+    let grid_h = (h / cell).ceil() as usize + 1;
+    let mut grid : Vec<Option<usize>> = vec![None; grid_w * grid_h];
+    let grid_ix = |p: Vec32| (p.x / cell) as usize + (p.y / cell) as usize * grid_w;
+
+    let mut points = Vec::new();
+    let mut active = Vec::new();
+    if w <= 0.0 || h <= 0.0 { return points }
+
+    let first = Vec32::new(rng.gen_range(0.0, w), rng.gen_range(0.0, h));
+    grid[grid_ix(first)] = Some(0);
+    points.push(first);
+    active.push(0);
+
+    while !active.is_empty() {
+        let active_ix = rng.gen_range(0, active.len());
+        let p = points[active[active_ix]];
+        let mut found = false;
+        for _ in 0..MAX_ATTEMPTS {
+            // Random point in the annulus r..2r around p
+            let angle = rng.gen_range(0.0, 2.0 * ::std::f32::consts::PI);
+            let dis = rng.gen_range(r, 2.0 * r);
+            let c = p + Vec32::new(angle.cos(), angle.sin()) * dis;
+            if c.x < 0.0 || c.y < 0.0 || c.x >= w || c.y >= h { continue }
+
+            // Check neighbouring cells for points that are too close
+            let (gx, gy) = ((c.x / cell) as i32, (c.y / cell) as i32);
+            let mut ok = true;
+            'search: for nx in (gx - 2).max(0)..(gx + 3).min(grid_w as i32) {
+                for ny in (gy - 2).max(0)..(gy + 3).min(grid_h as i32) {
+                    if let Some(other) = grid[nx as usize + ny as usize * grid_w] {
+                        if (points[other] - c).len() < r {
+                            ok = false;
+                            break 'search;
+                        }
+                    }
+                }
+            }
+            if !ok { continue }
+
+            grid[grid_ix(c)] = Some(points.len());
+            active.push(points.len());
+            points.push(c);
+            found = true;
+            break;
+        }
+        if !found {
+            active.swap_remove(active_ix);
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn points_are_spaced_and_in_bounds() {
+        let mut rng = StdRng::from_seed([3; 32]);
+        let points = poisson_disk(&mut rng, 16.0, 10.0, 1.5);
+        assert!(points.len() > 10);
+        for (ix, a) in points.iter().enumerate() {
+            assert!(a.x >= 0.0 && a.x < 16.0 && a.y >= 0.0 && a.y < 10.0);
+            for b in &points[ix+1..] {
+                assert!((*a - *b).len() >= 1.5);
+            }
+        }
+    }
+}